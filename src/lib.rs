@@ -5,8 +5,13 @@
 #[cfg(test)]
 #[macro_use]
 extern crate std;
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
 pub mod ser;
+pub mod with;
 
 #[doc(inline)]
-pub use self::ser::to_fmt;
+pub use self::ser::{
+    to_fmt, to_fmt_at_path, to_fmt_at_path_with_config, to_fmt_with_config, Emitter, Yaml,
+};
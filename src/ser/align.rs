@@ -0,0 +1,355 @@
+use core::fmt;
+
+use serde::ser;
+
+use crate::ser::key::KeySerializer;
+use crate::ser::probe::is_null;
+use crate::ser::{Config, Element, Serializer};
+
+/// Keys of a map beyond this many entries are not looked ahead at,
+/// values of wider keys beyond are written unaligned
+const LOOKAHEAD: usize = 16;
+
+/// Counts the characters written
+struct Width(usize);
+
+impl fmt::Write for Width {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.chars().count();
+        Ok(())
+    }
+}
+
+/// Width of a key as written, none for complex keys
+pub(crate) fn key_width<T: ser::Serialize + ?Sized>(key: &T, config: Config) -> Option<usize> {
+    let mut serializer = Serializer::new(Width(0), config);
+    serializer.emitter.preceding_element = Element::PreMappingKey;
+    let mut complex = false;
+    key.serialize(KeySerializer::new(&mut serializer, &mut complex)).ok()?;
+    if complex {
+        return None;
+    }
+    Some(serializer.emitter.writer.0)
+}
+
+/// Width of the widest key if the value is written as a mapping,
+/// struct fields are measured by their static names without serializing values
+pub(crate) fn width<T: ser::Serialize + ?Sized>(value: &T, config: Config) -> usize {
+    let mut width = 0;
+    value.serialize(Keys { width: &mut width, config }).ok();
+    width
+}
+
+struct Keys<'a> {
+    width: &'a mut usize,
+    config: Config,
+}
+
+/// Measures keys of mapping entries, ignores elements of other compound values
+struct Entries<'a> {
+    width: &'a mut usize,
+    config: Config,
+    count: usize,
+}
+
+impl<'a> Keys<'a> {
+    fn entries(self) -> Result<Entries<'a>, fmt::Error> {
+        Ok(Entries { width: self.width, config: self.config, count: 0 })
+    }
+}
+
+impl Entries<'_> {
+    /// Entries omitted for their null value don't count
+    fn measure<K, V>(&mut self, key: &K, value: &V) -> fmt::Result
+    where
+        K: ser::Serialize + ?Sized,
+        V: ser::Serialize + ?Sized,
+    {
+        if self.config.omit_null && is_null(value) {
+            return Ok(());
+        }
+        self.key(key)
+    }
+
+    fn key<K: ser::Serialize + ?Sized>(&mut self, key: &K) -> fmt::Result {
+        if let Some(width) = key_width(key, self.config) {
+            *self.width = core::cmp::max(*self.width, width);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for Keys<'a> {
+    type Ok = ();
+    type Error = fmt::Error;
+    type SerializeSeq = Entries<'a>;
+    type SerializeTuple = Entries<'a>;
+    type SerializeTupleStruct = Entries<'a>;
+    type SerializeTupleVariant = Entries<'a>;
+    type SerializeMap = Entries<'a>;
+    type SerializeStruct = Entries<'a>;
+    type SerializeStructVariant = Entries<'a>;
+
+    fn serialize_bool(self, _v: bool) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_i8(self, _v: i8) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_i16(self, _v: i16) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_i32(self, _v: i32) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_i64(self, _v: i64) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_u8(self, _v: u8) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_u16(self, _v: u16) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_u32(self, _v: u32) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_u64(self, _v: u64) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_f32(self, _v: f32) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_f64(self, _v: f64) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_char(self, _v: char) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_str(self, _v: &str) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_none(self) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_some<T: ser::Serialize + ?Sized>(self, value: &T) -> fmt::Result {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> fmt::Result
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ser::Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> fmt::Result {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Entries<'a>, fmt::Error> {
+        self.entries()
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Entries<'a>, fmt::Error> {
+        self.entries()
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Entries<'a>, fmt::Error> {
+        self.entries()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Entries<'a>, fmt::Error> {
+        self.entries()
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Entries<'a>, fmt::Error> {
+        self.entries()
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Entries<'a>, fmt::Error> {
+        self.entries()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Entries<'a>, fmt::Error> {
+        self.entries()
+    }
+
+    fn collect_str<T: fmt::Display + ?Sized>(self, _value: &T) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for Entries<'_> {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, _value: &T) -> fmt::Result {
+        Ok(())
+    }
+
+    fn end(self) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for Entries<'_> {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, _value: &T) -> fmt::Result {
+        Ok(())
+    }
+
+    fn end(self) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for Entries<'_> {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(&mut self, _value: &T) -> fmt::Result {
+        Ok(())
+    }
+
+    fn end(self) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for Entries<'_> {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(&mut self, _value: &T) -> fmt::Result {
+        Ok(())
+    }
+
+    fn end(self) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for Entries<'_> {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    /// Stops once enough entries are looked ahead at
+    fn serialize_key<T: ser::Serialize + ?Sized>(&mut self, key: &T) -> fmt::Result {
+        if self.count == LOOKAHEAD {
+            return Err(fmt::Error);
+        }
+        self.count += 1;
+        self.key(key)
+    }
+
+    fn serialize_value<T: ser::Serialize + ?Sized>(&mut self, _value: &T) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> fmt::Result
+    where
+        K: ser::Serialize + ?Sized,
+        V: ser::Serialize + ?Sized,
+    {
+        if self.config.omit_null && is_null(value) {
+            return Ok(());
+        }
+        self.serialize_key(key)
+    }
+
+    fn end(self) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for Entries<'_> {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> fmt::Result
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        self.measure(key, value)
+    }
+
+    fn end(self) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for Entries<'_> {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> fmt::Result
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        self.measure(key, value)
+    }
+
+    fn end(self) -> fmt::Result {
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use serde::ser;
+
+use crate::ser::marker::{Marked, Marker};
+
+/// Anchors a value, the first occurrence is written as `&name value`
+/// while later ones are written as alias `*name`
+///
+/// Name must be a valid YAML anchor, i.e. not empty and without whitespace or any
+/// of `,[]{}`, serializing fails otherwise. Other serializers see the value only.
+///
+/// ```
+/// use serde_yaml_core::ser::Anchored;
+///
+/// let base = [1, 2];
+/// let value = [Anchored::new("base", &base), Anchored::new("base", &base)];
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Anchored<T> {
+    name: &'static str,
+    value: T,
+}
+
+impl<T> Anchored<T> {
+    pub fn new(name: &'static str, value: T) -> Self {
+        Self { name, value }
+    }
+}
+
+impl<T: ser::Serialize> ser::Serialize for Anchored<T> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let marked = Marked { marker: Marker::Anchor, argument: self.name, value: &self.value };
+        marked.serialize(serializer)
+    }
+}
+
+/// Whether the name can be written as anchor and alias
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.chars().any(|c| c.is_whitespace() || c.is_control() || ",[]{}".contains(c))
+}
+
+/// Anchors are remembered without allocation, an anchor beyond capacity is
+/// defined again instead of being aliased, which is still valid YAML
+const MAX_ANCHORS: usize = 16;
+
+#[derive(Default)]
+pub(crate) struct Anchors {
+    names: [&'static str; MAX_ANCHORS],
+    len: usize,
+}
+
+impl Anchors {
+    /// Returns whether the anchor is defined already, otherwise defines it
+    pub(crate) fn define(&mut self, name: &'static str) -> bool {
+        if self.names[..self.len].contains(&name) {
+            return true;
+        }
+        if self.len < MAX_ANCHORS {
+            self.names[self.len] = name;
+            self.len += 1;
+        }
+        false
+    }
+}
+
+#[cfg(feature = "alloc")]
+pub(crate) use self::repeated::Repeated;
+
+#[cfg(feature = "alloc")]
+mod repeated {
+    use alloc::collections::BTreeMap;
+    use alloc::string::String;
+
+    #[derive(Default)]
+    pub(crate) struct Subtree {
+        pub(crate) count: usize,
+        pub(crate) id: Option<usize>,
+    }
+
+    /// Identical subtrees keyed by their YAML text, which are counted in a first pass
+    /// and then anchored and aliased in a second pass
+    #[derive(Default)]
+    pub(crate) struct Repeated {
+        pub(crate) counting: bool,
+        pub(crate) subtrees: BTreeMap<String, Subtree>,
+        pub(crate) next_id: usize,
+    }
+}
@@ -0,0 +1,51 @@
+use serde::ser;
+
+use crate::ser::marker::{Marked, Marker};
+
+/// Attaches a comment to a value, which is written above the struct field,
+/// mapping entry or sequence entry holding the value, or trailing on the same line
+///
+/// Other serializers see the value only.
+///
+/// ```
+/// use serde_yaml_core::ser::Commented;
+///
+/// #[derive(serde_derive::Serialize)]
+/// struct Uart {
+///     baudrate: Commented<u32>,
+///     parity: Commented<bool>,
+/// }
+///
+/// let uart = Uart {
+///     baudrate: Commented::new("Bits per second", 115200),
+///     parity: Commented::trailing("Even parity", false),
+/// };
+/// let mut yaml = String::new();
+/// serde_yaml_core::to_fmt(&mut yaml, &uart).unwrap();
+/// assert_eq!(yaml, "# Bits per second\nbaudrate: 115200\nparity: false # Even parity");
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Commented<T> {
+    comment: &'static str,
+    value: T,
+    trailing: bool,
+}
+
+impl<T> Commented<T> {
+    /// Comment lines above the value
+    pub fn new(comment: &'static str, value: T) -> Self {
+        Self { comment, value, trailing: false }
+    }
+
+    /// Comment trailing on the same line as a scalar value, or the key of a compound value
+    pub fn trailing(comment: &'static str, value: T) -> Self {
+        Self { comment, value, trailing: true }
+    }
+}
+
+impl<T: ser::Serialize> ser::Serialize for Commented<T> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let marker = if self.trailing { Marker::TrailingComment } else { Marker::Comment };
+        Marked { marker, argument: self.comment, value: &self.value }.serialize(serializer)
+    }
+}
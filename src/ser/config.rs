@@ -0,0 +1,253 @@
+use crate::ser::redact;
+
+/// Representation of none and unit values
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum NullStyle {
+    /// `key: null`
+    #[default]
+    Null,
+    /// `key: ~`
+    Tilde,
+    /// `key:`, falls back to `~` where an empty value isn't allowed, e.g. mapping keys
+    Empty,
+}
+
+/// Representation of enum variants
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum EnumStyle {
+    /// Unit variants as plain names and others as single-key mappings, e.g. `Move:\n  x: 1`
+    #[default]
+    External,
+    /// Variants as YAML local tags, e.g. `!Off` or `!Move\n  x: 1`,
+    /// unit variants as mapping keys are still written as plain names
+    Tag,
+}
+
+/// Line terminator of every line break written
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum LineEnding {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+impl LineEnding {
+    pub(crate) const fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Base of integers, non-decimal integers are prefixed, e.g. `0x1F`, `0o17` or `0b101`
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Radix {
+    #[default]
+    Decimal,
+    Hex,
+    Octal,
+    Binary,
+}
+
+/// Representation of floats
+///
+/// `Fixed` and `Significant` round half up the shortest digits reading back to the
+/// same float, e.g. 2.675 to `2.68` with 2 decimals, where `printf` rounds the exact
+/// binary value to `2.67`. Precision is at most 17, the number of significant digits
+/// of a `f64`.
+#[cfg(feature = "float")]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum FloatStyle {
+    /// Shortest representation which reads back to the same float, in exponent form
+    /// for very large or small magnitudes, e.g. `0.1` or `-2.3456789012345e-23`
+    #[default]
+    Shortest,
+    /// Shortest digits but never in exponent form, e.g. `-0.000000000000000000000023456789012345`
+    Plain,
+    /// Rounded to a fixed number of decimals, never in exponent form, e.g. `21.50`
+    Fixed(u8),
+    /// Rounded to a number of significant digits, in exponent form where `Shortest` is,
+    /// e.g. `21.5` or `-2.35e-23` with 3 digits
+    Significant(u8),
+}
+
+/// Serializer options
+///
+/// ```
+/// use serde_yaml_core::ser::Config;
+///
+/// let config = Config::new().quote_large_integers(true);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Config {
+    pub(crate) quote_large_integers: bool,
+    pub(crate) omit_null: bool,
+    pub(crate) null_style: NullStyle,
+    pub(crate) enum_style: EnumStyle,
+    pub(crate) header: Option<&'static str>,
+    pub(crate) ascii_only: bool,
+    pub(crate) line_ending: LineEnding,
+    pub(crate) final_newline: bool,
+    pub(crate) align_values: bool,
+    pub(crate) indent: usize,
+    pub(crate) flow: bool,
+    pub(crate) document_markers: bool,
+    pub(crate) unsigned_radix: Radix,
+    pub(crate) zero_pad: bool,
+    pub(crate) redact: &'static [&'static str],
+    #[cfg(feature = "float")]
+    pub(crate) float_style: FloatStyle,
+    #[cfg(feature = "alloc")]
+    pub(crate) auto_anchors: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Config {
+    pub const fn new() -> Self {
+        Self {
+            quote_large_integers: false,
+            omit_null: false,
+            null_style: NullStyle::Null,
+            enum_style: EnumStyle::External,
+            header: None,
+            ascii_only: false,
+            line_ending: LineEnding::Lf,
+            final_newline: false,
+            align_values: false,
+            indent: 2,
+            flow: false,
+            document_markers: false,
+            unsigned_radix: Radix::Decimal,
+            zero_pad: false,
+            redact: &[],
+            #[cfg(feature = "float")]
+            float_style: FloatStyle::Shortest,
+            #[cfg(feature = "alloc")]
+            auto_anchors: false,
+        }
+    }
+
+    /// Quote integers beyond 2^53, which can't be represented exactly by consumers
+    /// parsing YAML numbers into doubles
+    pub const fn quote_large_integers(mut self, quote: bool) -> Self {
+        self.quote_large_integers = quote;
+        self
+    }
+
+    /// Omit struct fields and map entries whose value is none or unit,
+    /// map entries are omitted only when serialized with `serialize_entry`
+    pub const fn omit_null(mut self, omit: bool) -> Self {
+        self.omit_null = omit;
+        self
+    }
+
+    pub const fn null_style(mut self, style: NullStyle) -> Self {
+        self.null_style = style;
+        self
+    }
+
+    pub const fn enum_style(mut self, style: EnumStyle) -> Self {
+        self.enum_style = style;
+        self
+    }
+
+    /// Comment lines at the beginning of the document
+    pub const fn header(mut self, comment: &'static str) -> Self {
+        self.header = Some(comment);
+        self
+    }
+
+    /// Double-quote strings, keys and chars containing non-ASCII characters,
+    /// which are escaped as `\uXXXX` or `\UXXXXXXXX`, as they are in comments.
+    /// Anchor names and `RawYaml` fragments can't be escaped, serializing fails
+    /// when they contain non-ASCII characters.
+    pub const fn ascii_only(mut self, ascii: bool) -> Self {
+        self.ascii_only = ascii;
+        self
+    }
+
+    pub const fn line_ending(mut self, ending: LineEnding) -> Self {
+        self.line_ending = ending;
+        self
+    }
+
+    /// Terminate the document with a line break, as POSIX text files and
+    /// concatenated documents expect
+    pub const fn final_newline(mut self, newline: bool) -> Self {
+        self.final_newline = newline;
+        self
+    }
+
+    /// Spaces per nesting level, at least 2 which is the width of `- `
+    pub const fn indent(mut self, width: usize) -> Self {
+        self.indent = if width < 2 { 2 } else { width };
+        self
+    }
+
+    /// Write every sequence, mapping and struct in flow style, e.g. `{a: [1, 2]}`
+    pub const fn flow(mut self, flow: bool) -> Self {
+        self.flow = flow;
+        self
+    }
+
+    /// Enclose the document in explicit markers, i.e. `---` and `...`
+    pub const fn document_markers(mut self, markers: bool) -> Self {
+        self.document_markers = markers;
+        self
+    }
+
+    /// Pad scalar values of block mappings to line up in a column, e.g. `name:    foo`,
+    /// at the cost of visiting the keys of every mapping once more ahead
+    pub const fn align_values(mut self, align: bool) -> Self {
+        self.align_values = align;
+        self
+    }
+
+    /// Base of unsigned integers, signed integers are always decimal
+    /// unless overridden per value
+    pub const fn unsigned_radix(mut self, radix: Radix) -> Self {
+        self.unsigned_radix = radix;
+        self
+    }
+
+    /// Zero-pad hexadecimal, octal and binary integers to the width of their type,
+    /// e.g. `0x001F` for `u16`
+    pub const fn zero_pad(mut self, pad: bool) -> Self {
+        self.zero_pad = pad;
+        self
+    }
+
+    /// Write `'***'` in place of values of matching keys or fields, e.g. passwords in logs
+    ///
+    /// Patterns without dots are names of keys at any depth, others are paths of keys
+    /// from the root where `*` matches any key, e.g. `&["password", "network.*.psk"]`.
+    /// Sequences and enum variants don't count as keys in paths. At most 32 patterns
+    /// may contain dots, more fail to compile in a `const` config and panic otherwise.
+    pub const fn redact(mut self, patterns: &'static [&'static str]) -> Self {
+        assert!(redact::paths(patterns) <= redact::MAX_PATHS, "more than 32 redacted paths");
+        self.redact = patterns;
+        self
+    }
+
+    #[cfg(feature = "float")]
+    pub const fn float_style(mut self, style: FloatStyle) -> Self {
+        self.float_style = style;
+        self
+    }
+
+    /// Anchor identical mappings and sequences automatically, at the cost of
+    /// serializing twice and holding the text of every subtree
+    #[cfg(feature = "alloc")]
+    pub const fn auto_anchors(mut self, auto: bool) -> Self {
+        self.auto_anchors = auto;
+        self
+    }
+}
@@ -0,0 +1,79 @@
+use core::fmt;
+
+use serde::ser;
+
+use crate::ser::{to_fmt_with_config, Config};
+
+/// Formats a value as YAML, e.g. inline in log messages without a separate buffer
+///
+/// Formatting flags refine the config, `{:#}` selects flow style, width selects
+/// indentation, e.g. `{:4}`, and `{:+}` encloses the document in explicit markers.
+///
+/// ```
+/// use serde_yaml_core::Yaml;
+///
+/// #[derive(serde_derive::Serialize)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let point = Point { x: 1, y: 2 };
+/// assert_eq!(format!("{}", Yaml::new(&point)), "x: 1\ny: 2");
+/// assert_eq!(format!("{:#}", Yaml::new(&point)), "{x: 1, y: 2}");
+/// assert_eq!(format!("{:+}", Yaml::new(&point)), "---\nx: 1\ny: 2\n...");
+/// ```
+///
+/// With the `defmt` feature it implements `defmt::Format` as well, writing the YAML
+/// in chunks as the serializer produces them, e.g. `defmt::info!("{}", Yaml::new(&point))`.
+#[derive(Copy, Clone, Debug)]
+pub struct Yaml<T> {
+    value: T,
+    config: Config,
+}
+
+impl<T> Yaml<T> {
+    pub fn new(value: T) -> Self {
+        Self { value, config: Config::new() }
+    }
+
+    pub fn with_config(value: T, config: Config) -> Self {
+        Self { value, config }
+    }
+}
+
+impl<T: ser::Serialize> fmt::Display for Yaml<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut config = self.config;
+        if f.alternate() {
+            config = config.flow(true);
+        }
+        if let Some(width) = f.width() {
+            config = config.indent(width);
+        }
+        if f.sign_plus() {
+            config = config.document_markers(true);
+        }
+        to_fmt_with_config(f, &self.value, config)
+    }
+}
+
+/// Forwards output of the serializer to defmt chunk by chunk
+#[cfg(feature = "defmt")]
+struct Chunks<'a>(defmt::Formatter<'a>);
+
+#[cfg(feature = "defmt")]
+impl fmt::Write for Chunks<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        defmt::write!(self.0, "{=str}", s);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T: ser::Serialize> defmt::Format for Yaml<T> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        // Output written up to an error is logged as is, defmt has no way to fail
+        to_fmt_with_config(Chunks(f), &self.value, self.config).ok();
+    }
+}
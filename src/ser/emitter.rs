@@ -0,0 +1,502 @@
+use core::fmt::{self, Write};
+
+use crate::ser::config::{Config, NullStyle};
+use crate::ser::marker::Override;
+use crate::ser::scalar;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Element {
+    SequenceEntry,
+    PreMappingKey,
+    MappingKey,
+    Property,
+    Literal,
+    Comment,
+    FlowEntry,
+    None,
+}
+
+/// Lines of a comment, broken at `\n`, `\r\n` and a lone `\r` alike
+/// since YAML reads each of them as a line break
+struct Lines<'a>(&'a str);
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let (line, rest) = self.0.split_at(self.0.find(['\r', '\n']).unwrap_or(self.0.len()));
+        self.0 = rest.strip_prefix("\r\n").or_else(|| rest.get(1..)).unwrap_or("");
+        Some(line)
+    }
+}
+
+/// Characters written as their escapes in comments, which can't be quoted
+fn is_escaped(c: char, ascii: bool) -> bool {
+    (c.is_control() && c != '\t')
+        || matches!(c, '\u{2028}' | '\u{2029}')
+        || (ascii && !c.is_ascii())
+}
+
+/// Collections can be nested this deep through `Emitter`
+const MAX_LEVELS: u32 = 32;
+
+/// Collections opened through `Emitter`, as bit sets indexed by nesting level
+#[derive(Copy, Clone, Default)]
+struct Levels {
+    mapping: u32,
+    flow: u32,
+    empty: u32,
+    /// Mappings whose key was written, expecting its value
+    pending: u32,
+    len: u32,
+    /// Whether the value of the document was begun
+    root: bool,
+}
+
+impl Levels {
+    fn top(&self) -> Option<u32> {
+        self.len.checked_sub(1)
+    }
+
+    fn is_mapping(&self, level: u32) -> bool {
+        self.mapping & (1 << level) != 0
+    }
+
+    fn is_flow(&self, level: u32) -> bool {
+        self.flow & (1 << level) != 0
+    }
+
+    /// Whether the collection is empty so far, it isn't afterwards
+    fn take_empty(&mut self, level: u32) -> bool {
+        let empty = self.empty & (1 << level) != 0;
+        self.empty &= !(1 << level);
+        empty
+    }
+
+    fn is_pending(&self, level: u32) -> bool {
+        self.pending & (1 << level) != 0
+    }
+
+    fn set_pending(&mut self, level: u32, pending: bool) {
+        let bit = 1 << level;
+        self.pending = if pending { self.pending | bit } else { self.pending & !bit };
+    }
+
+    fn push(&mut self, mapping: bool, flow: bool) {
+        let bit = 1 << self.len;
+        self.mapping = if mapping { self.mapping | bit } else { self.mapping & !bit };
+        self.flow = if flow { self.flow | bit } else { self.flow & !bit };
+        self.empty |= bit;
+        self.pending &= !bit;
+        self.len += 1;
+    }
+}
+
+/// Writes YAML event by event, taking care of indentation and quoting,
+/// for data which isn't `Serialize`
+///
+/// Values are a scalar or a collection from `begin_mapping` or `begin_sequence`
+/// up to the matching `end`, values of a mapping follow their `key`. Calls out of
+/// this order fail without writing anything, e.g. a value where a key is expected
+/// or a second value of the document.
+///
+/// ```
+/// use serde_yaml_core::ser::Emitter;
+///
+/// let mut yaml = String::new();
+/// let mut emitter = Emitter::new(&mut yaml);
+/// emitter.begin_mapping().unwrap();
+/// emitter.comment("Status register").unwrap();
+/// emitter.key("status").unwrap();
+/// emitter.plain("0x1F").unwrap();
+/// emitter.key("errors").unwrap();
+/// emitter.begin_sequence().unwrap();
+/// emitter.scalar("overrun").unwrap();
+/// emitter.end().unwrap();
+/// emitter.end().unwrap();
+/// assert_eq!(yaml, "# Status register\nstatus: 0x1F\nerrors:\n  - overrun");
+/// ```
+pub struct Emitter<W: Write> {
+    pub(crate) writer: W,
+    pub(crate) depth: usize,
+    pub(crate) preceding_element: Element,
+    pub(crate) config: Config,
+    /// Style of the upcoming node
+    pub(crate) style: Option<Override>,
+    pub(crate) flow: usize,
+    /// Padding of the upcoming value
+    pub(crate) pad: usize,
+    /// A block scalar ended its last line, the next line break is written already
+    pub(crate) line_ended: bool,
+    levels: Levels,
+}
+
+impl<W: Write> Emitter<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_config(writer, Config::new())
+    }
+
+    pub fn with_config(writer: W, config: Config) -> Self {
+        Self {
+            writer,
+            depth: 0,
+            preceding_element: Element::None,
+            config,
+            style: None,
+            flow: 0,
+            pad: 0,
+            line_ended: false,
+            levels: Levels::default(),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Explicit document start marker `---`, the document begins on the next line,
+    /// after the value of the previous document if any
+    pub fn document_start(&mut self) -> fmt::Result {
+        if self.levels.len > 0 {
+            return Err(fmt::Error);
+        }
+        if self.levels.root || self.preceding_element != Element::None {
+            self.newline()?;
+        }
+        self.levels.root = false;
+        self.str("---")?;
+        self.newline()?;
+        self.preceding_element = Element::None;
+        Ok(())
+    }
+
+    /// Begins a mapping, in flow style within flow collections or with `Config::flow`
+    pub fn begin_mapping(&mut self) -> fmt::Result {
+        self.begin(true)
+    }
+
+    /// Begins a sequence, in flow style within flow collections or with `Config::flow`
+    pub fn begin_sequence(&mut self) -> fmt::Result {
+        self.begin(false)
+    }
+
+    /// Key of the next entry of the innermost mapping, followed by its value
+    pub fn key(&mut self, key: &str) -> fmt::Result {
+        let level = self.levels.top().filter(|&level| self.levels.is_mapping(level));
+        let level = level.filter(|&level| !self.levels.is_pending(level)).ok_or(fmt::Error)?;
+        self.levels.set_pending(level, true);
+        let first = self.levels.take_empty(level);
+        self.separate(first || self.preceding_element == Element::Comment, self.is_flow(level))?;
+        self.write_key(key)?;
+        self.push();
+        Ok(())
+    }
+
+    /// Scalar value, quoted where it would read back as something else
+    pub fn scalar(&mut self, v: &str) -> fmt::Result {
+        self.entry()?;
+        self.indent(Element::Literal)?;
+        self.write_scalar(v)?;
+        self.exit();
+        Ok(())
+    }
+
+    /// Scalar value written as is, e.g. a number or boolean, which the caller
+    /// makes sure reads back as intended, fails for line breaks
+    pub fn plain(&mut self, v: &str) -> fmt::Result {
+        if v.contains(['\n', '\r']) {
+            return Err(fmt::Error);
+        }
+        self.entry()?;
+        self.indent(Element::Literal)?;
+        self.str(v)?;
+        self.exit();
+        Ok(())
+    }
+
+    /// Null value, written in the style of `Config::null_style`
+    pub fn null(&mut self) -> fmt::Result {
+        self.entry()?;
+        self.write_null()?;
+        self.exit();
+        Ok(())
+    }
+
+    /// Ends the innermost collection, fails after a key without value
+    pub fn end(&mut self) -> fmt::Result {
+        let level = self.levels.top().ok_or(fmt::Error)?;
+        if self.levels.is_pending(level) {
+            return Err(fmt::Error);
+        }
+        let mapping = self.levels.is_mapping(level);
+        let empty = self.levels.take_empty(level);
+        let flow = self.is_flow(level);
+        self.levels.len = level;
+        match (flow, mapping) {
+            (true, true) => self.end_flow('}')?,
+            (true, false) => self.end_flow(']')?,
+            (false, _) if empty => {
+                self.indent(Element::Literal)?;
+                self.str(if mapping { "{}" } else { "[]" })?;
+            }
+            (false, _) => (),
+        }
+        self.exit();
+        Ok(())
+    }
+
+    /// Comment lines above the upcoming entry, left out within flow collections
+    pub fn comment(&mut self, comment: &str) -> fmt::Result {
+        if self.flow > 0 {
+            return Ok(());
+        }
+        let entries = self.levels.top().is_some_and(|level| self.levels.empty & (1 << level) == 0);
+        if entries && self.preceding_element == Element::None {
+            self.newline()?;
+        }
+        self.comment_lines(comment)
+    }
+
+    fn is_flow(&self, level: u32) -> bool {
+        self.levels.is_flow(level)
+    }
+
+    fn begin(&mut self, mapping: bool) -> fmt::Result {
+        if self.levels.len == MAX_LEVELS {
+            return Err(fmt::Error);
+        }
+        self.entry()?;
+        let flow = self.flow > 0 || self.config.flow;
+        if flow {
+            self.begin_flow(if mapping { '{' } else { '[' })?;
+        }
+        self.levels.push(mapping, flow);
+        Ok(())
+    }
+
+    /// Sequence entry indicator ahead of a value in a sequence,
+    /// fails where no value is expected
+    fn entry(&mut self) -> fmt::Result {
+        let level = match self.levels.top() {
+            Some(level) if !self.levels.is_mapping(level) => level,
+            Some(level) if self.levels.is_pending(level) => {
+                self.levels.set_pending(level, false);
+                return Ok(());
+            }
+            Some(_) => return Err(fmt::Error),
+            None if self.levels.root => return Err(fmt::Error),
+            None => {
+                self.levels.root = true;
+                return Ok(());
+            }
+        };
+        let first = self.levels.take_empty(level);
+        let flow = self.is_flow(level);
+        self.separate(first || self.preceding_element == Element::Comment, flow)?;
+        if !flow {
+            self.indent(Element::SequenceEntry)?;
+            self.str("-")?;
+            self.push();
+        }
+        Ok(())
+    }
+
+    /// Back to the depth of the enclosing collection after a value
+    fn exit(&mut self) {
+        match self.levels.top() {
+            Some(level) if self.levels.is_mapping(level) || !self.is_flow(level) => self.pop(),
+            _ => (),
+        }
+    }
+
+    pub(crate) fn char(&mut self, c: char) -> fmt::Result {
+        self.writer.write_char(c)
+    }
+
+    pub(crate) fn str(&mut self, string: &str) -> fmt::Result {
+        self.writer.write_str(string)
+    }
+
+    pub(crate) fn newline(&mut self) -> fmt::Result {
+        if core::mem::take(&mut self.line_ended) {
+            return Ok(());
+        }
+        self.writer.write_str(self.config.line_ending.as_str())
+    }
+
+    /// Indentation of a line at the current depth
+    pub(crate) fn margin(&mut self) -> fmt::Result {
+        write!(self.writer, "{:indent$}", "", indent = self.depth * self.config.indent)
+    }
+
+    pub(crate) fn write_scalar(&mut self, v: &str) -> fmt::Result {
+        scalar::write(&mut self.writer, v, self.flow > 0, self.config.ascii_only)
+    }
+
+    /// Write a mapping key along with the indicator
+    pub(crate) fn write_key(&mut self, key: &str) -> fmt::Result {
+        self.indent(Element::MappingKey)?;
+        self.write_scalar(key)?;
+        self.char(':')
+    }
+
+    pub(crate) fn tag(&mut self, tag: &str) -> fmt::Result {
+        self.indent(Element::Property)?;
+        scalar::write_tag(&mut self.writer, tag)
+    }
+
+    pub(crate) fn indent(&mut self, mut element: Element) -> fmt::Result {
+        if element != Element::Property {
+            self.style = None;
+        }
+        let pad = core::mem::take(&mut self.pad);
+        if self.flow > 0 {
+            match (self.preceding_element, element) {
+                (Element::PreMappingKey, Element::Literal) => element = Element::MappingKey,
+                (Element::FlowEntry, _) | (Element::PreMappingKey, _) => (),
+                _ => self.char(' ')?,
+            }
+            self.preceding_element = element;
+            return Ok(());
+        }
+        match (self.preceding_element, element) {
+            (Element::MappingKey, Element::Literal) | (Element::MappingKey, Element::Property) => {
+                write!(self.writer, "{:width$}", "", width = pad + 1)?
+            }
+            // Entries of a compound entry line up with its first entry
+            (Element::SequenceEntry, Element::MappingKey)
+            | (Element::SequenceEntry, Element::PreMappingKey)
+            | (Element::SequenceEntry, Element::SequenceEntry) => {
+                write!(self.writer, "{:width$}", "", width = self.config.indent - 1)?
+            }
+            (Element::SequenceEntry, _)
+            | (Element::Property, Element::Property)
+            | (Element::Property, Element::Literal) => self.char(' ')?,
+            (Element::PreMappingKey, Element::Literal) => element = Element::MappingKey,
+            (Element::Literal, Element::MappingKey) => (),
+            (Element::None, _) => self.margin()?,
+            _ => {
+                self.newline()?;
+                self.margin()?
+            }
+        }
+        self.preceding_element = element;
+        Ok(())
+    }
+
+    pub(crate) fn begin_flow(&mut self, open: char) -> fmt::Result {
+        self.indent(Element::Literal)?;
+        self.char(open)?;
+        self.flow += 1;
+        self.preceding_element = Element::FlowEntry;
+        Ok(())
+    }
+
+    /// Separate entries of a collection, on new lines or with commas in flow style
+    pub(crate) fn separate(&mut self, first: bool, flow: bool) -> fmt::Result {
+        if !flow {
+            return if first { Ok(()) } else { self.newline() };
+        }
+        if !first {
+            self.str(", ")?;
+        }
+        self.preceding_element = Element::FlowEntry;
+        Ok(())
+    }
+
+    pub(crate) fn end_flow(&mut self, close: char) -> fmt::Result {
+        self.flow -= 1;
+        self.preceding_element = Element::Literal;
+        self.char(close)
+    }
+
+    pub(crate) fn write_null(&mut self) -> fmt::Result {
+        match (self.config.null_style, self.preceding_element) {
+            (NullStyle::Null, _) => (),
+            (NullStyle::Empty, Element::PreMappingKey) | (NullStyle::Tilde, _) => {
+                self.indent(Element::Literal)?;
+                return self.char('~');
+            }
+            (NullStyle::Empty, _) if self.flow > 0 => {
+                self.indent(Element::Literal)?;
+                return self.char('~');
+            }
+            (NullStyle::Empty, _) => {
+                self.preceding_element = Element::Literal;
+                return Ok(());
+            }
+        }
+        self.indent(Element::Literal)?;
+        self.str("null")
+    }
+
+    pub(crate) fn comment_lines(&mut self, comment: &str) -> fmt::Result {
+        for line in Lines(comment) {
+            if self.preceding_element != Element::None {
+                self.newline()?;
+            }
+            self.margin()?;
+            self.char('#')?;
+            if !line.is_empty() {
+                self.char(' ')?;
+                self.comment_text(line)?;
+            }
+            self.preceding_element = Element::Comment;
+        }
+        Ok(())
+    }
+
+    /// Comments can't be quoted, control characters and with `ascii_only` non-ASCII
+    /// characters are written as their escapes, e.g. `\x07` or `\u00B0`
+    fn comment_text(&mut self, line: &str) -> fmt::Result {
+        let ascii = self.config.ascii_only;
+        if !line.chars().any(|c| is_escaped(c, ascii)) {
+            return self.str(line);
+        }
+        for c in line.chars() {
+            match c as u32 {
+                _ if !is_escaped(c, ascii) => self.char(c)?,
+                code @ 0..=0xFF if c.is_control() => write!(self.writer, "\\x{:02X}", code)?,
+                code @ 0..=0xFFFF => write!(self.writer, "\\u{:04X}", code)?,
+                code => write!(self.writer, "\\U{:08X}", code)?,
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn trailing_comment(&mut self, comment: &str) -> fmt::Result {
+        if self.line_ended {
+            // On lines of their own below a block scalar, indented less than its content
+            let depth = self.depth;
+            self.depth = depth.saturating_sub(1);
+            let result = self.comment_lines(comment);
+            self.depth = depth;
+            return result;
+        }
+        let mut lines = Lines(comment);
+        if let Some(line) = lines.next() {
+            if self.preceding_element != Element::None {
+                self.char(' ')?;
+            }
+            self.char('#')?;
+            if !line.is_empty() {
+                self.char(' ')?;
+                self.comment_text(line)?;
+            }
+            self.preceding_element = Element::Comment;
+        }
+        self.comment_lines(lines.0)
+    }
+
+    pub(crate) fn push(&mut self) {
+        self.depth += 1;
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.depth -= 1;
+        self.preceding_element = Element::None;
+    }
+}
@@ -0,0 +1,242 @@
+use core::fmt::{self, Write};
+
+use serde::ser;
+
+use crate::ser::config::FloatStyle;
+use crate::ser::marker::{Marked, Marker, Override};
+
+/// Float written in the given style regardless of `Config::float_style`
+///
+/// Precision is limited as in `FloatStyle`. Other serializers see the float only.
+///
+/// ```
+/// use serde_yaml_core::ser::Float;
+///
+/// #[derive(serde_derive::Serialize)]
+/// struct Sensor {
+///     temperature: Float<f32>,
+///     current: Float<f64>,
+/// }
+///
+/// let sensor = Sensor {
+///     temperature: Float::fixed(1, 21.4999),
+///     current: Float::significant(3, -2.3456789012345e-23),
+/// };
+/// let mut yaml = String::new();
+/// serde_yaml_core::to_fmt(&mut yaml, &sensor).unwrap();
+/// assert_eq!(yaml, "temperature: 21.5\ncurrent: -2.35e-23");
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Float<T> {
+    style: FloatStyle,
+    value: T,
+}
+
+impl<T> Float<T> {
+    pub fn new(style: FloatStyle, value: T) -> Self {
+        Self { style, value }
+    }
+
+    pub fn shortest(value: T) -> Self {
+        Self::new(FloatStyle::Shortest, value)
+    }
+
+    pub fn plain(value: T) -> Self {
+        Self::new(FloatStyle::Plain, value)
+    }
+
+    pub fn fixed(decimals: u8, value: T) -> Self {
+        Self::new(FloatStyle::Fixed(decimals), value)
+    }
+
+    pub fn significant(digits: u8, value: T) -> Self {
+        Self::new(FloatStyle::Significant(digits), value)
+    }
+}
+
+/// Precision carried as argument name, as the marker name can't hold it
+const PRECISIONS: [&str; MAX_DIGITS + 1] = [
+    "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15", "16",
+    "17",
+];
+
+impl<T: ser::Serialize> ser::Serialize for Float<T> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let precision = match self.style {
+            FloatStyle::Fixed(precision) | FloatStyle::Significant(precision) => precision,
+            FloatStyle::Shortest | FloatStyle::Plain => 0,
+        };
+        let argument = PRECISIONS[(precision as usize).min(MAX_DIGITS)];
+        let marker = Marker::Override(Override::Float(self.style));
+        Marked { marker, argument, value: &self.value }.serialize(serializer)
+    }
+}
+
+/// Style with the precision carried by the argument of a `Float` marker
+pub(crate) fn with_precision(style: FloatStyle, argument: &str) -> FloatStyle {
+    let precision = argument.parse().unwrap_or(0);
+    match style {
+        FloatStyle::Fixed(_) => FloatStyle::Fixed(precision),
+        FloatStyle::Significant(_) => FloatStyle::Significant(precision),
+        style => style,
+    }
+}
+
+/// Shortest representation of a float never has more digits
+const MAX_DIGITS: usize = 17;
+
+/// Decimal digits of a float, i.e. `0.d1d2...dn × 10^point`, without trailing zeros
+struct Decimal {
+    negative: bool,
+    digits: [u8; MAX_DIGITS],
+    len: usize,
+    point: i32,
+}
+
+impl Decimal {
+    /// Parses the shortest representation formatted by ryu, e.g. `-1.25e-7`
+    fn parse(formatted: &str) -> Self {
+        let (negative, formatted) = match formatted.strip_prefix('-') {
+            Some(formatted) => (true, formatted),
+            None => (false, formatted),
+        };
+        let (mantissa, exponent) = match formatted.split_once('e') {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse().unwrap_or(0)),
+            None => (formatted, 0),
+        };
+        let mut decimal = Self { negative, digits: [0; MAX_DIGITS], len: 0, point: exponent };
+        let mut fraction = false;
+        for byte in mantissa.bytes() {
+            match byte {
+                b'.' => fraction = true,
+                b'0' if decimal.len == 0 => decimal.point -= fraction as i32,
+                digit => {
+                    if decimal.len < MAX_DIGITS {
+                        decimal.digits[decimal.len] = digit - b'0';
+                        decimal.len += 1;
+                    }
+                    decimal.point += !fraction as i32;
+                }
+            }
+        }
+        decimal.trim();
+        decimal
+    }
+
+    fn trim(&mut self) {
+        while self.len > 0 && self.digits[self.len - 1] == 0 {
+            self.len -= 1;
+        }
+    }
+
+    /// Rounds half up to `n` leading digits
+    fn round(&mut self, n: i32) {
+        if n >= self.len as i32 {
+            return;
+        }
+        if n < 0 {
+            self.len = 0;
+            return;
+        }
+        let up = self.digits[n as usize] >= 5;
+        self.len = n as usize;
+        if up {
+            self.carry();
+        }
+        self.trim();
+    }
+
+    fn carry(&mut self) {
+        while self.len > 0 && self.digits[self.len - 1] == 9 {
+            self.len -= 1;
+        }
+        match self.len {
+            0 => {
+                self.digits[0] = 1;
+                self.len = 1;
+                self.point += 1;
+            }
+            len => self.digits[len - 1] += 1,
+        }
+    }
+
+    fn digit(&self, index: i32) -> char {
+        match index {
+            index if index >= 0 && (index as usize) < self.len => {
+                char::from(b'0' + self.digits[index as usize])
+            }
+            _ => '0',
+        }
+    }
+
+    /// Number of decimals needed, at least one so that it still reads as a float
+    fn decimals(&self) -> usize {
+        (self.len as i32 - self.point).max(1) as usize
+    }
+
+    fn write_sign<W: Write>(&self, w: &mut W) -> fmt::Result {
+        match self.negative && self.len > 0 {
+            true => w.write_char('-'),
+            false => Ok(()),
+        }
+    }
+
+    fn write_plain<W: Write>(&self, w: &mut W, decimals: usize) -> fmt::Result {
+        self.write_sign(w)?;
+        if self.point <= 0 {
+            w.write_char('0')?;
+        }
+        for index in 0..self.point {
+            w.write_char(self.digit(index))?;
+        }
+        if decimals > 0 {
+            w.write_char('.')?;
+        }
+        for index in 0..decimals as i32 {
+            w.write_char(self.digit(self.point + index))?;
+        }
+        Ok(())
+    }
+
+    fn write_exponent<W: Write>(&self, w: &mut W) -> fmt::Result {
+        self.write_sign(w)?;
+        w.write_char(self.digit(0))?;
+        if self.len > 1 {
+            w.write_char('.')?;
+        }
+        for index in 1..self.len as i32 {
+            w.write_char(self.digit(index))?;
+        }
+        write!(w, "e{}", self.point - 1)
+    }
+}
+
+/// Writes a float formatted by ryu in the given style, using only integer
+/// arithmetic on its digits, precision is limited to `MAX_DIGITS`
+pub(crate) fn write<W: Write>(w: &mut W, formatted: &str, style: FloatStyle) -> fmt::Result {
+    match formatted {
+        "NaN" => return w.write_str(".nan"),
+        "inf" => return w.write_str(".inf"),
+        "-inf" => return w.write_str("-.inf"),
+        _ => (),
+    }
+    let mut decimal = match style {
+        FloatStyle::Shortest => return w.write_str(formatted),
+        _ => Decimal::parse(formatted),
+    };
+    match style {
+        FloatStyle::Fixed(decimals) => {
+            let decimals = (decimals as usize).min(MAX_DIGITS);
+            decimal.round(decimal.point + decimals as i32);
+            decimal.write_plain(w, decimals)
+        }
+        FloatStyle::Significant(digits) => {
+            decimal.round((digits as usize).clamp(1, MAX_DIGITS) as i32);
+            match formatted.contains('e') {
+                true => decimal.write_exponent(w),
+                false => decimal.write_plain(w, decimal.decimals()),
+            }
+        }
+        _ => decimal.write_plain(w, decimal.decimals()),
+    }
+}
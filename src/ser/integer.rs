@@ -0,0 +1,123 @@
+use core::fmt;
+
+use serde::ser;
+
+use crate::ser::config::Radix;
+use crate::ser::marker::{Marker, Override};
+
+/// Integer written in the given radix regardless of `Config::unsigned_radix`,
+/// optionally zero-padded to the width of its type
+///
+/// Other serializers see the integer only.
+///
+/// ```
+/// use serde_yaml_core::ser::Integer;
+///
+/// #[derive(serde_derive::Serialize)]
+/// struct Register {
+///     address: Integer<u32>,
+///     mode: Integer<u8>,
+///     flags: Integer<u8>,
+/// }
+///
+/// let register = Register {
+///     address: Integer::hex(0x4000).padded(),
+///     mode: Integer::octal(0o17),
+///     flags: Integer::binary(0b101).padded(),
+/// };
+/// let mut yaml = String::new();
+/// serde_yaml_core::to_fmt(&mut yaml, &register).unwrap();
+/// assert_eq!(yaml, "address: 0x00004000\nmode: 0o17\nflags: 0b00000101");
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Integer<T> {
+    radix: Radix,
+    padded: bool,
+    value: T,
+}
+
+impl<T> Integer<T> {
+    pub fn new(radix: Radix, value: T) -> Self {
+        Self { radix, padded: false, value }
+    }
+
+    pub fn decimal(value: T) -> Self {
+        Self::new(Radix::Decimal, value)
+    }
+
+    pub fn hex(value: T) -> Self {
+        Self::new(Radix::Hex, value)
+    }
+
+    pub fn octal(value: T) -> Self {
+        Self::new(Radix::Octal, value)
+    }
+
+    pub fn binary(value: T) -> Self {
+        Self::new(Radix::Binary, value)
+    }
+
+    /// Zero-pad to the width of the integer type, decimal integers aren't padded
+    pub fn padded(mut self) -> Self {
+        self.padded = true;
+        self
+    }
+}
+
+impl<T: ser::Serialize> ser::Serialize for Integer<T> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let marker = Marker::Override(Override::Radix { radix: self.radix, padded: self.padded });
+        serializer.serialize_newtype_struct(marker.name(), &self.value)
+    }
+}
+
+/// Primitive integers as seen by `Serializer::integer`
+pub(crate) trait Primitive: Copy + fmt::Display {
+    const BITS: u32;
+    const SIGNED: bool;
+
+    fn is_negative(self) -> bool;
+    fn magnitude(self) -> u128;
+}
+
+macro_rules! signed {
+    ($($ty:ty),*) => {
+        $(
+            impl Primitive for $ty {
+                const BITS: u32 = <$ty>::BITS;
+                const SIGNED: bool = true;
+
+                fn is_negative(self) -> bool {
+                    self < 0
+                }
+
+                fn magnitude(self) -> u128 {
+                    self.unsigned_abs() as u128
+                }
+            }
+        )*
+    };
+}
+
+signed!(i8, i16, i32, i64, i128);
+
+macro_rules! unsigned {
+    ($($ty:ty),*) => {
+        $(
+            impl Primitive for $ty {
+                const BITS: u32 = <$ty>::BITS;
+                const SIGNED: bool = false;
+
+                fn is_negative(self) -> bool {
+                    false
+                }
+
+                fn magnitude(self) -> u128 {
+                    self as u128
+                }
+            }
+        )*
+    };
+}
+
+unsigned!(u8, u16, u32, u64, u128);
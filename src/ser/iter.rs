@@ -0,0 +1,65 @@
+use serde::ser;
+
+use crate::ser::marker::{Marker, Override};
+
+/// Items of an iterator serialized as a sequence, without collecting them first
+///
+/// The iterator is cloned each time the value is serialized, since the serializer
+/// may look ahead at values before writing them. With `flow_up_to` sequences whose
+/// `size_hint` bounds them to at most that many items are written in flow style.
+/// Other serializers see a sequence.
+///
+/// ```
+/// use serde_yaml_core::ser::SerializeIter;
+///
+/// let readings = [310, 0, 295, 0];
+/// let errors = SerializeIter::new(readings.iter().filter(|&&r| r == 0));
+/// let mut yaml = String::new();
+/// serde_yaml_core::to_fmt(&mut yaml, &errors).unwrap();
+/// assert_eq!(yaml, "- 0\n- 0");
+///
+/// let recent = SerializeIter::new(readings[2..].iter()).flow_up_to(4);
+/// let mut yaml = String::new();
+/// serde_yaml_core::to_fmt(&mut yaml, &recent).unwrap();
+/// assert_eq!(yaml, "[295, 0]");
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct SerializeIter<I> {
+    iter: I,
+    flow_up_to: Option<usize>,
+}
+
+impl<I> SerializeIter<I> {
+    pub fn new(iter: I) -> Self {
+        Self { iter, flow_up_to: None }
+    }
+
+    /// Flow style for sequences whose upper bound of `size_hint` is at most `len`
+    pub fn flow_up_to(mut self, len: usize) -> Self {
+        self.flow_up_to = Some(len);
+        self
+    }
+}
+
+impl<I> ser::Serialize for SerializeIter<I>
+where
+    I: Iterator + Clone,
+    I::Item: ser::Serialize,
+{
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (lower, upper) = self.iter.size_hint();
+        if let (Some(upper), Some(len)) = (upper, self.flow_up_to) {
+            if upper <= len {
+                let items = Self::new(self.iter.clone());
+                let name = Marker::Override(Override::Flow).name();
+                return serializer.serialize_newtype_struct(name, &items);
+            }
+        }
+        let len = if upper == Some(lower) { Some(lower) } else { None };
+        let mut seq = serializer.serialize_seq(len)?;
+        for item in self.iter.clone() {
+            ser::SerializeSeq::serialize_element(&mut seq, &item)?;
+        }
+        ser::SerializeSeq::end(seq)
+    }
+}
@@ -0,0 +1,200 @@
+use core::fmt;
+
+use serde::ser;
+
+use crate::ser::{Element, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant};
+use crate::ser::{SerializeTupleVariant, Serializer};
+
+/// Serializes mapping keys, scalars are written in place while compound keys
+/// are written in explicit form, i.e. `? key` followed by `: value`
+pub(crate) struct KeySerializer<'a, W: fmt::Write> {
+    serializer: &'a mut Serializer<W>,
+    complex: &'a mut bool,
+}
+
+impl<'a, W: fmt::Write> KeySerializer<'a, W> {
+    pub(crate) fn new(serializer: &'a mut Serializer<W>, complex: &'a mut bool) -> Self {
+        Self { serializer, complex }
+    }
+
+    fn complex(self) -> Result<&'a mut Serializer<W>, fmt::Error> {
+        if self.serializer.emitter.flow > 0 {
+            return Ok(self.serializer);
+        }
+        *self.complex = true;
+        self.serializer.emitter.str("?")?;
+        self.serializer.emitter.preceding_element = Element::SequenceEntry;
+        self.serializer.emitter.push();
+        Ok(self.serializer)
+    }
+}
+
+impl<'a, W: fmt::Write> ser::Serializer for KeySerializer<'a, W> {
+    type Ok = ();
+    type Error = fmt::Error;
+    type SerializeSeq = SerializeSeq<'a, W>;
+    type SerializeTuple = SerializeSeq<'a, W>;
+    type SerializeTupleStruct = SerializeSeq<'a, W>;
+    type SerializeTupleVariant = SerializeTupleVariant<'a, W>;
+    type SerializeMap = SerializeMap<'a, W>;
+    type SerializeStruct = SerializeStruct<'a, W>;
+    type SerializeStructVariant = SerializeStructVariant<'a, W>;
+
+    fn serialize_bool(self, v: bool) -> fmt::Result {
+        self.serializer.serialize_bool(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> fmt::Result {
+        self.serializer.serialize_i8(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> fmt::Result {
+        self.serializer.serialize_i16(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> fmt::Result {
+        self.serializer.serialize_i32(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> fmt::Result {
+        self.serializer.serialize_i64(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> fmt::Result {
+        self.serializer.serialize_i128(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> fmt::Result {
+        self.serializer.serialize_u8(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> fmt::Result {
+        self.serializer.serialize_u16(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> fmt::Result {
+        self.serializer.serialize_u32(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> fmt::Result {
+        self.serializer.serialize_u64(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> fmt::Result {
+        self.serializer.serialize_u128(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> fmt::Result {
+        self.serializer.serialize_f32(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> fmt::Result {
+        self.serializer.serialize_f64(v)
+    }
+
+    fn serialize_char(self, c: char) -> fmt::Result {
+        self.serializer.serialize_char(c)
+    }
+
+    fn serialize_str(self, v: &str) -> fmt::Result {
+        self.serializer.serialize_str(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> fmt::Result {
+        self.serializer.serialize_bytes(v)
+    }
+
+    fn serialize_none(self) -> fmt::Result {
+        self.serializer.serialize_none()
+    }
+
+    fn serialize_some<T: ser::Serialize + ?Sized>(self, value: &T) -> fmt::Result {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> fmt::Result {
+        self.serializer.serialize_unit()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> fmt::Result {
+        self.serializer.serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> fmt::Result {
+        self.serializer.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> fmt::Result
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ser::Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> fmt::Result {
+        self.complex()?.serialize_newtype_variant(name, variant_index, variant, value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, fmt::Error> {
+        self.complex()?.serialize_seq(len)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, fmt::Error> {
+        self.complex()?.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, fmt::Error> {
+        self.complex()?.serialize_tuple_struct(name, len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, fmt::Error> {
+        self.complex()?.serialize_tuple_variant(name, variant_index, variant, len)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, fmt::Error> {
+        self.complex()?.serialize_map(len)
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, fmt::Error> {
+        self.complex()?.serialize_struct(name, len)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, fmt::Error> {
+        self.complex()?.serialize_struct_variant(name, variant_index, variant, len)
+    }
+
+    fn collect_str<T: fmt::Display + ?Sized>(self, value: &T) -> fmt::Result {
+        self.serializer.collect_str(value)
+    }
+}
@@ -2,16 +2,48 @@ use core::fmt;
 
 use serde::ser;
 
+use crate::ser::key::KeySerializer;
+use crate::ser::probe::is_null;
+use crate::ser::redact::Redaction;
 use crate::ser::{Element, Serializer};
 
 pub struct SerializeMap<'a, W: fmt::Write> {
     serializer: &'a mut Serializer<W>,
     empty: bool,
+    flow: bool,
+    complex_key: bool,
+    align: usize,
+    /// Redaction within the value of the key written, none if it's redacted
+    redaction: Option<Redaction>,
 }
 
 impl<'a, W: fmt::Write> SerializeMap<'a, W> {
-    pub(crate) fn new(serializer: &'a mut Serializer<W>) -> Self {
-        Self { serializer, empty: true }
+    pub(crate) fn new(serializer: &'a mut Serializer<W>, flow: bool) -> Self {
+        let align = core::mem::take(&mut serializer.align);
+        let redaction = Some(serializer.redaction);
+        Self { serializer, empty: true, flow, complex_key: false, align, redaction }
+    }
+
+    fn separate(&mut self) -> fmt::Result {
+        self.serializer.emitter.separate(self.empty, self.flow)?;
+        self.empty = false;
+        Ok(())
+    }
+
+    fn key<T: ser::Serialize + ?Sized>(&mut self, key: &T) -> fmt::Result {
+        self.serializer.emitter.indent(Element::PreMappingKey)?;
+        self.complex_key = false;
+        key.serialize(KeySerializer::new(&mut *self.serializer, &mut self.complex_key))?;
+        if self.complex_key {
+            self.serializer.emitter.pop();
+            self.serializer.emitter.newline()?;
+            self.serializer.emitter.indent(Element::MappingKey)?;
+        } else {
+            self.serializer.align(self.align, key);
+        }
+        self.redaction = self.serializer.enter(key);
+        self.serializer.emitter.push();
+        self.serializer.emitter.str(":")
     }
 }
 
@@ -20,25 +52,37 @@ impl<'a, W: fmt::Write> ser::SerializeMap for SerializeMap<'a, W> {
     type Error = fmt::Error;
 
     fn serialize_key<T: ser::Serialize + ?Sized>(&mut self, key: &T) -> fmt::Result {
-        if !self.empty {
-            self.serializer.char('\n')?;
-        }
-        self.empty = false;
-        self.serializer.indent(Element::PreMappingKey)?;
-        key.serialize(&mut *self.serializer)?;
-        self.serializer.push();
-        self.serializer.str(":")
+        self.separate()?;
+        self.key(key)
     }
 
     fn serialize_value<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
-        value.serialize(&mut *self.serializer)?;
-        self.serializer.pop();
+        self.serializer.entry_value(self.redaction, value)?;
+        self.serializer.emitter.pop();
         Ok(())
     }
 
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> fmt::Result
+    where
+        K: ser::Serialize + ?Sized,
+        V: ser::Serialize + ?Sized,
+    {
+        if self.serializer.emitter.config.omit_null && is_null(value) {
+            return Ok(());
+        }
+        self.separate()?;
+        self.serializer.comment_ahead(value)?;
+        self.key(key)?;
+        self.serialize_value(value)
+    }
+
     fn end(self) -> fmt::Result {
+        if self.flow {
+            return self.serializer.emitter.end_flow('}');
+        }
         if self.empty {
-            self.serializer.str("{}")?;
+            self.serializer.emitter.indent(Element::Literal)?;
+            self.serializer.emitter.str("{}")?;
         }
         Ok(())
     }
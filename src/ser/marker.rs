@@ -0,0 +1,122 @@
+use serde::ser;
+
+#[cfg(feature = "float")]
+use crate::ser::config::FloatStyle;
+use crate::ser::config::Radix;
+
+/// Style of the next value overridden by `serde_yaml_core::with`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Override {
+    Literal,
+    Folded,
+    DoubleQuoted,
+    Flow,
+    Radix {
+        radix: Radix,
+        padded: bool,
+    },
+    /// Followed by the precision as argument
+    #[cfg(feature = "float")]
+    Float(FloatStyle),
+}
+
+/// Wrapper types recognized by their newtype struct name
+///
+/// Anchor and comments are followed by another newtype struct carrying the argument
+/// as its name, e.g. an anchor name, raw YAML is followed by a string,
+/// while an override applies to the value directly, except for float precision.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Marker {
+    Anchor,
+    Comment,
+    TrailingComment,
+    Raw,
+    Override(Override),
+}
+
+const MARKERS: &[Marker] = &[
+    Marker::Anchor,
+    Marker::Comment,
+    Marker::TrailingComment,
+    Marker::Raw,
+    Marker::Override(Override::Literal),
+    Marker::Override(Override::Folded),
+    Marker::Override(Override::DoubleQuoted),
+    Marker::Override(Override::Flow),
+    Marker::Override(Override::Radix { radix: Radix::Decimal, padded: false }),
+    Marker::Override(Override::Radix { radix: Radix::Hex, padded: false }),
+    Marker::Override(Override::Radix { radix: Radix::Octal, padded: false }),
+    Marker::Override(Override::Radix { radix: Radix::Binary, padded: false }),
+    Marker::Override(Override::Radix { radix: Radix::Decimal, padded: true }),
+    Marker::Override(Override::Radix { radix: Radix::Hex, padded: true }),
+    Marker::Override(Override::Radix { radix: Radix::Octal, padded: true }),
+    Marker::Override(Override::Radix { radix: Radix::Binary, padded: true }),
+    #[cfg(feature = "float")]
+    Marker::Override(Override::Float(FloatStyle::Shortest)),
+    #[cfg(feature = "float")]
+    Marker::Override(Override::Float(FloatStyle::Plain)),
+    #[cfg(feature = "float")]
+    Marker::Override(Override::Float(FloatStyle::Fixed(0))),
+    #[cfg(feature = "float")]
+    Marker::Override(Override::Float(FloatStyle::Significant(0))),
+];
+
+impl Marker {
+    pub(crate) const fn name(self) -> &'static str {
+        match self {
+            Marker::Anchor => "$serde_yaml_core::Anchored",
+            Marker::Comment => "$serde_yaml_core::Commented",
+            Marker::TrailingComment => "$serde_yaml_core::TrailingCommented",
+            Marker::Raw => "$serde_yaml_core::RawYaml",
+            Marker::Override(Override::Literal) => "$serde_yaml_core::with::literal",
+            Marker::Override(Override::Folded) => "$serde_yaml_core::with::folded",
+            Marker::Override(Override::DoubleQuoted) => "$serde_yaml_core::with::double_quoted",
+            Marker::Override(Override::Flow) => "$serde_yaml_core::with::flow",
+            Marker::Override(Override::Radix { radix, padded: false }) => match radix {
+                Radix::Decimal => "$serde_yaml_core::with::decimal",
+                Radix::Hex => "$serde_yaml_core::with::hex",
+                Radix::Octal => "$serde_yaml_core::with::octal",
+                Radix::Binary => "$serde_yaml_core::with::binary",
+            },
+            Marker::Override(Override::Radix { radix, padded: true }) => match radix {
+                Radix::Decimal => "$serde_yaml_core::Integer::decimal",
+                Radix::Hex => "$serde_yaml_core::Integer::padded_hex",
+                Radix::Octal => "$serde_yaml_core::Integer::padded_octal",
+                Radix::Binary => "$serde_yaml_core::Integer::padded_binary",
+            },
+            #[cfg(feature = "float")]
+            Marker::Override(Override::Float(style)) => match style {
+                FloatStyle::Shortest => "$serde_yaml_core::Float::shortest",
+                FloatStyle::Plain => "$serde_yaml_core::Float::plain",
+                FloatStyle::Fixed(_) => "$serde_yaml_core::Float::fixed",
+                FloatStyle::Significant(_) => "$serde_yaml_core::Float::significant",
+            },
+        }
+    }
+
+    pub(crate) fn of(name: &str) -> Option<Self> {
+        MARKERS.iter().copied().find(|marker| marker.name() == name)
+    }
+}
+
+/// Serialized as `marker(argument(value))`, other serializers see the value only
+pub(crate) struct Marked<'a, T: ?Sized> {
+    pub(crate) marker: Marker,
+    pub(crate) argument: &'static str,
+    pub(crate) value: &'a T,
+}
+
+struct Argument<'a, T: ?Sized>(&'static str, &'a T);
+
+impl<T: ser::Serialize + ?Sized> ser::Serialize for Argument<'_, T> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(self.0, self.1)
+    }
+}
+
+impl<T: ser::Serialize + ?Sized> ser::Serialize for Marked<'_, T> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let argument = Argument(self.argument, self.value);
+        serializer.serialize_newtype_struct(self.marker.name(), &argument)
+    }
+}
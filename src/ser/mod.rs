@@ -3,63 +3,300 @@
 use core::fmt;
 
 use serde::ser;
-use serde::ser::SerializeStruct as _;
 
+mod align;
+mod anchor;
+mod comment;
+mod config;
+mod display;
+mod emitter;
+#[cfg(feature = "float")]
+mod float;
+mod integer;
+mod iter;
+mod key;
 mod map;
+pub(crate) mod marker;
+mod path;
+mod probe;
+mod raw;
+mod redact;
+mod scalar;
 mod sequence;
+mod stream;
 mod struct_;
-mod unreachable;
 
+pub use self::anchor::Anchored;
+use self::anchor::Anchors;
+pub use self::comment::Commented;
+#[cfg(feature = "float")]
+pub use self::config::FloatStyle;
+pub use self::config::{Config, EnumStyle, LineEnding, NullStyle, Radix};
+pub use self::display::Yaml;
+use self::emitter::Element;
+pub use self::emitter::Emitter;
+#[cfg(feature = "float")]
+pub use self::float::Float;
+pub use self::integer::Integer;
+use self::integer::Primitive;
+pub use self::iter::SerializeIter;
 use self::map::SerializeMap;
-use self::sequence::SerializeSeq;
+use self::marker::{Marker, Override};
+pub use self::raw::RawYaml;
+use self::redact::Redaction;
+use self::sequence::{SerializeSeq, SerializeTupleVariant};
+pub use self::stream::SeqWriter;
 use self::struct_::{SerializeStruct, SerializeStructVariant};
-use self::unreachable::Unreachable;
-
-#[derive(Copy, Clone, Debug, PartialEq)]
-enum Element {
-    SequenceEntry,
-    PreMappingKey,
-    MappingKey,
-    Literal,
-    None,
-}
 
 pub(crate) struct Serializer<W: fmt::Write> {
-    writer: W,
-    depth: usize,
-    preceding_element: Element,
+    emitter: Emitter<W>,
+    anchors: Anchors,
+    pending: Option<Marker>,
+    comment_written: bool,
+    /// Width of the widest key of the upcoming mapping
+    align: usize,
+    redaction: Redaction,
+    #[cfg(feature = "alloc")]
+    repeated: Option<anchor::Repeated>,
+}
+
+/// Discards output, for counting repeated subtrees
+#[cfg(feature = "alloc")]
+struct Discard;
+
+#[cfg(feature = "alloc")]
+impl fmt::Write for Discard {
+    fn write_str(&mut self, _s: &str) -> fmt::Result {
+        Ok(())
+    }
 }
 
+/// Largest integer a double can represent exactly
+const MAX_SAFE_INTEGER: u128 = 1 << 53;
+
 impl<W: fmt::Write> Serializer<W> {
-    fn char(&mut self, c: char) -> fmt::Result {
-        self.writer.write_char(c)
+    fn new(writer: W, config: Config) -> Self {
+        Self {
+            emitter: Emitter::with_config(writer, config),
+            anchors: Anchors::default(),
+            pending: None,
+            comment_written: false,
+            align: 0,
+            redaction: Redaction::default(),
+            #[cfg(feature = "alloc")]
+            repeated: None,
+        }
+    }
+
+    /// Unsigned integers follow `Config::unsigned_radix` unless overridden,
+    /// signed integers are decimal unless overridden
+    fn integer<I: Primitive>(&mut self, v: I) -> fmt::Result {
+        let (negative, magnitude, bits) = (v.is_negative(), v.magnitude(), I::BITS);
+        let (radix, padded) = match self.emitter.style {
+            Some(Override::Radix { radix, padded }) => {
+                (radix, padded || self.emitter.config.zero_pad)
+            }
+            _ if I::SIGNED => (Radix::Decimal, false),
+            _ => (self.emitter.config.unsigned_radix, self.emitter.config.zero_pad),
+        };
+        self.emitter.indent(Element::Literal)?;
+        let quote = self.emitter.config.quote_large_integers && magnitude > MAX_SAFE_INTEGER;
+        if quote {
+            self.emitter.char('\'')?;
+        }
+        if negative && radix != Radix::Decimal {
+            self.emitter.char('-')?;
+        }
+        let w = &mut self.emitter.writer;
+        match (radix, padded) {
+            (Radix::Decimal, _) => write!(w, "{}", v)?,
+            (Radix::Hex, false) => write!(w, "0x{:X}", magnitude)?,
+            (Radix::Hex, true) => write!(w, "0x{:01$X}", magnitude, bits.div_ceil(4) as usize)?,
+            (Radix::Octal, false) => write!(w, "0o{:o}", magnitude)?,
+            (Radix::Octal, true) => write!(w, "0o{:01$o}", magnitude, bits.div_ceil(3) as usize)?,
+            (Radix::Binary, false) => write!(w, "0b{:b}", magnitude)?,
+            (Radix::Binary, true) => write!(w, "0b{:01$b}", magnitude, bits as usize)?,
+        }
+        if quote {
+            self.emitter.char('\'')?;
+        }
+        Ok(())
+    }
+
+    /// Writes a float formatted by ryu, in `Config::float_style` unless overridden
+    #[cfg(feature = "float")]
+    fn float(&mut self, formatted: &str) -> fmt::Result {
+        let style = match self.emitter.style {
+            Some(Override::Float(style)) => style,
+            _ => self.emitter.config.float_style,
+        };
+        self.emitter.indent(Element::Literal)?;
+        float::write(&mut self.emitter.writer, formatted, style)
+    }
+
+    /// Whether the upcoming collection is in flow style,
+    /// collections nested in a flow collection are always in flow style
+    fn is_flow(&mut self) -> bool {
+        self.emitter.style.take() == Some(Override::Flow)
+            || self.emitter.flow > 0
+            || self.emitter.config.flow
+    }
+
+    /// Serialize a sequence element or mapping value
+    fn value<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
+        self.look_ahead(value);
+        #[cfg(feature = "alloc")]
+        {
+            if self.repeated.is_some() && probe::kind(value) == probe::Kind::Compound {
+                return self.repeated(value);
+            }
+        }
+        value.serialize(self)
+    }
+
+    /// Measure keys of the value ahead, in case it's a mapping to be aligned
+    fn look_ahead<T: ser::Serialize + ?Sized>(&mut self, value: &T) {
+        if self.emitter.config.align_values && self.emitter.flow == 0 {
+            self.align = align::width(value, self.emitter.config);
+        }
     }
 
-    fn str(&mut self, string: &str) -> fmt::Result {
-        self.writer.write_str(string)
+    /// Redaction within the value of the key, none if the value is redacted
+    fn enter<K: ser::Serialize + ?Sized>(&self, key: &K) -> Option<Redaction> {
+        self.redaction.enter(self.emitter.config.redact, key)
     }
 
-    fn indent(&mut self, mut element: Element) -> fmt::Result {
-        match (self.preceding_element, element) {
-            (Element::SequenceEntry, _) | (Element::MappingKey, Element::Literal) => {
-                self.char(' ')?
+    /// Write the value of a key entered, or the placeholder if it's redacted
+    fn entry_value<T>(&mut self, redaction: Option<Redaction>, value: &T) -> fmt::Result
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        let redaction = match redaction {
+            Some(redaction) => redaction,
+            None => {
+                self.emitter.indent(Element::Literal)?;
+                return self.emitter.write_scalar(redact::PLACEHOLDER);
+            }
+        };
+        let outer = core::mem::replace(&mut self.redaction, redaction);
+        self.value(value)?;
+        self.redaction = outer;
+        Ok(())
+    }
+
+    /// Pad the value of a key written, so that values of the mapping line up
+    fn align<T: ser::Serialize + ?Sized>(&mut self, width: usize, key: &T) {
+        if width > 0 {
+            let key = align::key_width(key, self.emitter.config).unwrap_or(width);
+            self.emitter.pad = width.saturating_sub(key);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    fn repeated<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
+        let mut text = alloc::string::String::new();
+        document(&mut text, value, self.emitter.config.auto_anchors(false), self.redaction)?;
+        let repeated = self.repeated.as_mut().unwrap();
+        if repeated.counting {
+            let subtree = repeated.subtrees.entry(text).or_default();
+            subtree.count += 1;
+            if subtree.count > 1 {
+                // Only subtrees nested in the first occurrence will be written
+                return self.emitter.indent(Element::Literal);
             }
-            (Element::PreMappingKey, Element::Literal) => element = Element::MappingKey,
-            (Element::Literal, Element::MappingKey) => (),
-            (Element::None, _) => write!(self.writer, "{:indent$}", "", indent = self.depth * 2)?,
-            _ => write!(self.writer, "\n{:indent$}", "", indent = self.depth * 2)?,
+            return value.serialize(self);
+        }
+        let subtree = match repeated.subtrees.get_mut(&text) {
+            Some(subtree) if subtree.count > 1 => subtree,
+            _ => return value.serialize(self),
+        };
+        if let Some(id) = subtree.id {
+            self.emitter.indent(Element::Literal)?;
+            return write!(self.emitter.writer, "*id{:03}", id);
+        }
+        repeated.next_id += 1;
+        subtree.id = Some(repeated.next_id);
+        let id = repeated.next_id;
+        self.emitter.indent(Element::Property)?;
+        write!(self.emitter.writer, "&id{:03}", id)?;
+        value.serialize(self)
+    }
+
+    fn anchored<T: ser::Serialize + ?Sized>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> fmt::Result {
+        let ascii = self.emitter.config.ascii_only;
+        if !anchor::is_valid_name(name) || (ascii && !name.is_ascii()) {
+            return Err(fmt::Error);
+        }
+        if self.anchors.define(name) {
+            self.emitter.indent(Element::Literal)?;
+            self.emitter.char('*')?;
+            return self.emitter.str(name);
+        }
+        self.emitter.indent(Element::Property)?;
+        self.emitter.char('&')?;
+        self.emitter.str(name)?;
+        value.serialize(self)
+    }
+
+    /// Write the header comment and the document start marker, if configured
+    fn begin_document(&mut self) -> fmt::Result {
+        if let Some(header) = self.emitter.config.header {
+            self.emitter.comment_lines(header)?;
+        }
+        if self.emitter.config.document_markers {
+            self.emitter.document_start()?;
+        }
+        Ok(())
+    }
+
+    /// Write the document end marker and the final newline, if configured
+    fn end_document(&mut self) -> fmt::Result {
+        if self.emitter.config.document_markers {
+            self.emitter.newline()?;
+            self.emitter.str("...")?;
+        }
+        if self.emitter.config.final_newline {
+            self.emitter.newline()?;
         }
-        self.preceding_element = element;
         Ok(())
     }
 
-    fn push(&mut self) {
-        self.depth += 1;
+    /// Write comment of the upcoming value above its key or sequence entry indicator
+    fn comment_ahead<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
+        if self.emitter.flow > 0 {
+            return Ok(());
+        }
+        if let Some(comment) = probe::comment(value) {
+            self.emitter.comment_lines(comment)?;
+            self.comment_written = true;
+        }
+        Ok(())
     }
 
-    fn pop(&mut self) {
-        self.depth -= 1;
-        self.preceding_element = Element::None;
+    fn commented<T>(&mut self, marker: Marker, comment: &str, value: &T) -> fmt::Result
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        if self.emitter.flow > 0 {
+            return value.serialize(self);
+        }
+        if marker == Marker::Comment {
+            if !self.comment_written {
+                self.emitter.comment_lines(comment)?;
+            }
+            self.comment_written = false;
+            return value.serialize(self);
+        }
+        if probe::kind(value) == probe::Kind::Compound {
+            self.emitter.trailing_comment(comment)?;
+            return value.serialize(self);
+        }
+        value.serialize(&mut *self)?;
+        self.emitter.trailing_comment(comment)
     }
 }
 
@@ -69,102 +306,119 @@ impl<'a, W: fmt::Write> ser::Serializer for &'a mut Serializer<W> {
     type Error = fmt::Error;
     type SerializeSeq = SerializeSeq<'a, W>;
     type SerializeTuple = SerializeSeq<'a, W>;
-    type SerializeTupleStruct = Unreachable;
-    type SerializeTupleVariant = Unreachable;
+    type SerializeTupleStruct = SerializeSeq<'a, W>;
+    type SerializeTupleVariant = SerializeTupleVariant<'a, W>;
     type SerializeMap = SerializeMap<'a, W>;
     type SerializeStruct = SerializeStruct<'a, W>;
     type SerializeStructVariant = SerializeStructVariant<'a, W>;
 
     fn serialize_bool(self, v: bool) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        self.str(if v { "true" } else { "false" })
+        self.emitter.indent(Element::Literal)?;
+        self.emitter.str(if v { "true" } else { "false" })
     }
 
     fn serialize_i8(self, v: i8) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        write!(self.writer, "{}", v)
+        self.integer(v)
     }
 
     fn serialize_i16(self, v: i16) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        write!(self.writer, "{}", v)
+        self.integer(v)
     }
 
     fn serialize_i32(self, v: i32) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        write!(self.writer, "{}", v)
+        self.integer(v)
     }
 
     fn serialize_i64(self, v: i64) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        write!(self.writer, "{}", v)
+        self.integer(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> fmt::Result {
+        self.integer(v)
     }
 
     fn serialize_u8(self, v: u8) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        write!(self.writer, "{}", v)
+        self.integer(v)
     }
 
     fn serialize_u16(self, v: u16) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        write!(self.writer, "{}", v)
+        self.integer(v)
     }
 
     fn serialize_u32(self, v: u32) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        write!(self.writer, "{}", v)
+        self.integer(v)
     }
 
     fn serialize_u64(self, v: u64) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        write!(self.writer, "{}", v)
+        self.integer(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> fmt::Result {
+        self.integer(v)
     }
 
+    #[cfg(feature = "float")]
     fn serialize_f32(self, v: f32) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        self.str(ryu::Buffer::new().format(v))
+        self.float(ryu::Buffer::new().format(v))
     }
 
+    #[cfg(feature = "float")]
     fn serialize_f64(self, v: f64) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        self.str(ryu::Buffer::new().format(v))
+        self.float(ryu::Buffer::new().format(v))
+    }
+
+    /// Floats are unsupported without the `float` feature
+    #[cfg(not(feature = "float"))]
+    fn serialize_f32(self, _v: f32) -> fmt::Result {
+        Err(fmt::Error)
+    }
+
+    #[cfg(not(feature = "float"))]
+    fn serialize_f64(self, _v: f64) -> fmt::Result {
+        Err(fmt::Error)
     }
 
     fn serialize_char(self, c: char) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        self.char(c)
+        self.serialize_str(c.encode_utf8(&mut [0u8; 4]))
     }
 
     fn serialize_str(self, v: &str) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        let mut quote = false;
-        match v.chars().next().unwrap_or(' ') {
-            '{' | '[' | ' ' | '&' | '*' | '#' | ',' | '>' | '!' | '%' | '@' => quote = true,
-            _ => (),
-        };
-        match v.chars().last().unwrap_or(' ') {
-            '}' | ']' | ' ' | ':' => quote = true,
-            _ => (),
-        };
-        quote |= v.chars().all(|c| c.is_digit(10) || c == ':');
-        if quote {
-            self.char('\'')?;
+        if self.pending == Some(Marker::Raw) {
+            self.pending = None;
+            return self.raw(v);
         }
-        self.str(v)?;
-        if quote {
-            self.char('\'')?;
+        let style = self.emitter.style;
+        let ascii = self.emitter.config.ascii_only;
+        self.emitter.indent(Element::Literal)?;
+        match style {
+            Some(Override::DoubleQuoted) => {
+                scalar::write_double_quoted(&mut self.emitter.writer, v, ascii)
+            }
+            Some(Override::Literal) | Some(Override::Folded) => {
+                if self.emitter.flow > 0
+                    || !scalar::is_block_compatible(v)
+                    || (ascii && !v.is_ascii())
+                {
+                    return scalar::write_double_quoted(&mut self.emitter.writer, v, ascii);
+                }
+                let indent = core::cmp::max(self.emitter.depth, 1) * self.emitter.config.indent;
+                let folded = style == Some(Override::Folded);
+                let eol = self.emitter.config.line_ending.as_str();
+                scalar::write_block(&mut self.emitter.writer, v, folded, indent, eol)?;
+                self.emitter.line_ended = v.ends_with('\n');
+                Ok(())
+            }
+            _ => self.emitter.write_scalar(v),
         }
-        Ok(())
     }
 
     fn serialize_bytes(self, v: &[u8]) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        self.str(unsafe { core::str::from_utf8_unchecked(v) })
+        self.emitter.indent(Element::Literal)?;
+        self.emitter.str(unsafe { core::str::from_utf8_unchecked(v) })
     }
 
     fn serialize_none(self) -> fmt::Result {
-        self.indent(Element::Literal)?;
-        self.str("null")
+        self.emitter.write_null()
     }
 
     fn serialize_some<T: ser::Serialize + ?Sized>(self, value: &T) -> fmt::Result {
@@ -185,30 +439,71 @@ impl<'a, W: fmt::Write> ser::Serializer for &'a mut Serializer<W> {
         _variant_index: u32,
         variant: &'static str,
     ) -> fmt::Result {
-        self.serialize_str(variant)
+        match self.emitter.config.enum_style {
+            EnumStyle::External => self.serialize_str(variant),
+            EnumStyle::Tag => {
+                self.emitter.tag(variant)?;
+                self.emitter.preceding_element = Element::Literal;
+                Ok(())
+            }
+        }
     }
 
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> fmt::Result
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> fmt::Result
     where
         T: ser::Serialize + ?Sized,
     {
-        value.serialize(self)
+        match self.pending.take() {
+            Some(Marker::Anchor) => self.anchored(name, value),
+            #[cfg(feature = "float")]
+            Some(Marker::Override(Override::Float(style))) => {
+                self.emitter.style = Some(Override::Float(float::with_precision(style, name)));
+                value.serialize(self)
+            }
+            Some(Marker::Raw) | Some(Marker::Override(_)) => value.serialize(self),
+            Some(marker) => self.commented(marker, name, value),
+            None => {
+                match Marker::of(name) {
+                    #[cfg(feature = "float")]
+                    marker @ Some(Marker::Override(Override::Float(_))) => self.pending = marker,
+                    Some(Marker::Override(style)) => self.emitter.style = Some(style),
+                    marker => self.pending = marker,
+                }
+                value.serialize(self)
+            }
+        }
     }
 
     fn serialize_newtype_variant<T: ser::Serialize + ?Sized>(
-        mut self,
+        self,
         _name: &'static str,
         _variant_index: u32,
         variant: &'static str,
         value: &T,
     ) -> fmt::Result {
-        let mut s = SerializeStruct::new(&mut self);
-        s.serialize_field(variant, value)?;
-        s.end()
+        if self.emitter.config.enum_style == EnumStyle::Tag {
+            self.emitter.tag(variant)?;
+            return value.serialize(self);
+        }
+        if self.is_flow() {
+            self.emitter.begin_flow('{')?;
+            self.emitter.write_key(variant)?;
+            self.value(value)?;
+            return self.emitter.end_flow('}');
+        }
+        self.emitter.write_key(variant)?;
+        self.emitter.push();
+        self.value(value)?;
+        self.emitter.pop();
+        Ok(())
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, fmt::Error> {
-        Ok(SerializeSeq::new(self))
+        let flow = self.is_flow();
+        if flow {
+            self.emitter.begin_flow('[')?;
+        }
+        Ok(SerializeSeq::new(self, flow))
     }
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, fmt::Error> {
@@ -218,23 +513,43 @@ impl<'a, W: fmt::Write> ser::Serializer for &'a mut Serializer<W> {
     fn serialize_tuple_struct(
         self,
         _name: &'static str,
-        _len: usize,
+        len: usize,
     ) -> Result<Self::SerializeTupleStruct, fmt::Error> {
-        unreachable!()
+        self.serialize_seq(Some(len))
     }
 
     fn serialize_tuple_variant(
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant, fmt::Error> {
-        unreachable!()
+        let flow = self.is_flow();
+        if self.emitter.config.enum_style == EnumStyle::Tag {
+            self.emitter.tag(variant)?;
+            if flow {
+                self.emitter.begin_flow('[')?;
+            }
+            return Ok(SerializeTupleVariant::new(self, flow, false));
+        }
+        if flow {
+            self.emitter.begin_flow('{')?;
+            self.emitter.write_key(variant)?;
+            self.emitter.begin_flow('[')?;
+            return Ok(SerializeTupleVariant::new(self, flow, true));
+        }
+        self.emitter.write_key(variant)?;
+        self.emitter.push();
+        Ok(SerializeTupleVariant::new(self, flow, true))
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, fmt::Error> {
-        Ok(SerializeMap::new(self))
+        let flow = self.is_flow();
+        if flow {
+            self.emitter.begin_flow('{')?;
+        }
+        Ok(SerializeMap::new(self, flow))
     }
 
     fn serialize_struct(
@@ -242,7 +557,11 @@ impl<'a, W: fmt::Write> ser::Serializer for &'a mut Serializer<W> {
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, fmt::Error> {
-        Ok(SerializeStruct::new(self))
+        let flow = self.is_flow();
+        if flow {
+            self.emitter.begin_flow('{')?;
+        }
+        Ok(SerializeStruct::new(self, flow))
     }
 
     fn serialize_struct_variant(
@@ -252,32 +571,181 @@ impl<'a, W: fmt::Write> ser::Serializer for &'a mut Serializer<W> {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, fmt::Error> {
-        self.indent(Element::MappingKey)?;
-        write!(self.writer, "{}:", variant)?;
-        self.push();
-        Ok(SerializeStructVariant::new(self))
+        let flow = self.is_flow();
+        if self.emitter.config.enum_style == EnumStyle::Tag {
+            self.emitter.tag(variant)?;
+            if flow {
+                self.emitter.begin_flow('{')?;
+            }
+            return Ok(SerializeStructVariant::new(self, flow, false));
+        }
+        if flow {
+            self.emitter.begin_flow('{')?;
+            self.emitter.write_key(variant)?;
+            self.emitter.begin_flow('{')?;
+            return Ok(SerializeStructVariant::new(self, flow, true));
+        }
+        self.emitter.write_key(variant)?;
+        self.emitter.push();
+        Ok(SerializeStructVariant::new(self, flow, true))
     }
 
-    fn collect_str<T: fmt::Display + ?Sized>(self, _value: &T) -> fmt::Result {
-        unreachable!()
+    /// Text fitting the stack buffer is written like any string, longer text
+    /// is streamed double-quoted since its style can't be resolved upfront
+    fn collect_str<T: fmt::Display + ?Sized>(self, value: &T) -> fmt::Result {
+        let mut buffer = scalar::Buffer::new();
+        if fmt::write(&mut buffer, format_args!("{}", value)).is_ok() {
+            return self.serialize_str(buffer.as_str());
+        }
+        if self.pending == Some(Marker::Raw) {
+            return Err(fmt::Error);
+        }
+        self.emitter.indent(Element::Literal)?;
+        let ascii = self.emitter.config.ascii_only;
+        self.emitter.char('"')?;
+        fmt::write(
+            &mut scalar::Escaped::new(&mut self.emitter.writer, ascii),
+            format_args!("{}", value),
+        )?;
+        self.emitter.char('"')
     }
 }
 
 /// Create a serializable formatter
 pub fn to_fmt<W: fmt::Write, T: ser::Serialize + ?Sized>(w: W, value: &T) -> fmt::Result {
-    let mut serializer = Serializer { writer: w, depth: 0, preceding_element: Element::None };
-    value.serialize(&mut serializer)
+    to_fmt_with_config(w, value, Config::default())
+}
+
+/// Create a serializable formatter with specified config
+pub fn to_fmt_with_config<W, T>(w: W, value: &T, config: Config) -> fmt::Result
+where
+    W: fmt::Write,
+    T: ser::Serialize + ?Sized,
+{
+    document(w, value, config, Redaction::root(config.redact)?)
+}
+
+/// Write the value as a document, redacted as if the keys leading to it were written
+fn document<W, T>(w: W, value: &T, config: Config, redaction: Redaction) -> fmt::Result
+where
+    W: fmt::Write,
+    T: ser::Serialize + ?Sized,
+{
+    let mut serializer = Serializer::new(w, config);
+    serializer.redaction = redaction;
+    #[cfg(feature = "alloc")]
+    {
+        if config.auto_anchors {
+            let mut counter = Serializer::new(Discard, config);
+            counter.redaction = redaction;
+            counter.repeated = Some(anchor::Repeated { counting: true, ..Default::default() });
+            value.serialize(&mut counter)?;
+            let mut repeated = counter.repeated.take().unwrap_or_default();
+            repeated.counting = false;
+            serializer.repeated = Some(repeated);
+        }
+    }
+    serializer.begin_document()?;
+    serializer.comment_ahead(value)?;
+    serializer.look_ahead(value);
+    value.serialize(&mut serializer)?;
+    serializer.end_document()
+}
+
+/// Serialize only the value at the path, e.g. `network.wifi[0]`, as a document of its own
+///
+/// Keys of mappings and fields of structs are separated by dots, elements of
+/// sequences are indexed in brackets. Externally tagged enum variants are entered
+/// through their name, a path ending at the name writes the fields of the variant.
+/// Nothing is buffered, everything aside from the value is skipped, and serializing
+/// fails when nothing is at the path.
+///
+/// ```
+/// #[derive(serde_derive::Serialize)]
+/// struct Wifi<'a> {
+///     ssid: &'a str,
+///     channel: u8,
+/// }
+///
+/// #[derive(serde_derive::Serialize)]
+/// struct Network<'a> {
+///     wifi: [Wifi<'a>; 2],
+/// }
+///
+/// #[derive(serde_derive::Serialize)]
+/// struct Config<'a> {
+///     network: Network<'a>,
+/// }
+///
+/// let wifi = [Wifi { ssid: "home", channel: 6 }, Wifi { ssid: "lab", channel: 11 }];
+/// let config = Config { network: Network { wifi } };
+/// let mut yaml = String::new();
+/// serde_yaml_core::to_fmt_at_path(&mut yaml, &config, "network.wifi[1]").unwrap();
+/// assert_eq!(yaml, "ssid: lab\nchannel: 11");
+/// ```
+pub fn to_fmt_at_path<W, T>(w: W, value: &T, path: &str) -> fmt::Result
+where
+    W: fmt::Write,
+    T: ser::Serialize + ?Sized,
+{
+    to_fmt_at_path_with_config(w, value, path, Config::default())
+}
+
+/// Serialize only the value at the path with specified config
+pub fn to_fmt_at_path_with_config<W, T>(
+    mut w: W,
+    value: &T,
+    path: &str,
+    config: Config,
+) -> fmt::Result
+where
+    W: fmt::Write,
+    T: ser::Serialize + ?Sized,
+{
+    match path::walk(&mut w, value, path, config, Redaction::root(config.redact)?)? {
+        true => Ok(()),
+        false => Err(fmt::Error),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use serde_derive::Serialize;
 
+    use super::{
+        Anchored, Commented, Config, EnumStyle, Integer, LineEnding, NullStyle, Radix, RawYaml,
+        SeqWriter, SerializeIter, Yaml,
+    };
+    #[cfg(feature = "float")]
+    use super::{Float, FloatStyle};
+
     struct Wrapper<T: serde::Serialize>(T);
 
     impl<T: serde::Serialize> core::fmt::Display for Wrapper<T> {
         fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-            crate::to_fmt(f, &self.0)
+            write!(f, "{}", Yaml::new(&self.0))
+        }
+    }
+
+    struct WithConfig<T: serde::Serialize>(T, Config);
+
+    impl<T: serde::Serialize> core::fmt::Display for WithConfig<T> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            write!(f, "{}", Yaml::with_config(&self.0, self.1))
+        }
+    }
+
+    struct Map<'a, K, V>(&'a [(K, V)]);
+
+    impl<'a, K: serde::Serialize, V: serde::Serialize> serde::Serialize for Map<'a, K, V> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeMap;
+
+            let mut map = serializer.serialize_map(Some(self.0.len()))?;
+            for (key, value) in self.0.iter() {
+                map.serialize_entry(key, value)?;
+            }
+            map.end()
         }
     }
 
@@ -321,6 +789,116 @@ mod tests {
         assert_eq!(format!("{}", Wrapper("1:2")), "'1:2'"); // clock format number
     }
 
+    #[test]
+    fn str_quoting() {
+        assert_eq!(format!("{}", Wrapper("a: b")), "'a: b'");
+        assert_eq!(format!("{}", Wrapper("a:b")), "a:b");
+        assert_eq!(format!("{}", Wrapper("a #b")), "'a #b'");
+        assert_eq!(format!("{}", Wrapper("a#b")), "a#b");
+        assert_eq!(format!("{}", Wrapper("- x")), "'- x'");
+        assert_eq!(format!("{}", Wrapper("-x")), "-x");
+        assert_eq!(format!("{}", Wrapper("-")), "'-'");
+        assert_eq!(format!("{}", Wrapper(" x")), "' x'");
+        assert_eq!(format!("{}", Wrapper("'x'")), "'''x'''");
+        assert_eq!(format!("{}", Wrapper("it's")), "it's");
+        assert_eq!(format!("{}", Wrapper("true")), "'true'");
+        assert_eq!(format!("{}", Wrapper("null")), "'null'");
+        assert_eq!(format!("{}", Wrapper("~")), "'~'");
+        assert_eq!(format!("{}", Wrapper("-1.5")), "'-1.5'");
+        assert_eq!(format!("{}", Wrapper("1e3")), "'1e3'");
+        assert_eq!(format!("{}", Wrapper("0x1F")), "'0x1F'");
+        assert_eq!(format!("{}", Wrapper(".inf")), "'.inf'");
+        assert_eq!(format!("{}", Wrapper("1_000")), "'1_000'");
+        assert_eq!(format!("{}", Wrapper("-0x_FF")), "'-0x_FF'");
+        assert_eq!(format!("{}", Wrapper("1_000.5")), "'1_000.5'");
+        assert_eq!(format!("{}", Wrapper("snake_case")), "snake_case");
+        assert_eq!(format!("{}", Wrapper("---")), "'---'");
+        assert_eq!(format!("{}", Wrapper("...")), "'...'");
+        assert_eq!(format!("{}", Wrapper("--- a")), "'--- a'");
+        assert_eq!(format!("{}", Wrapper(["..."])), "- '...'");
+        assert_eq!(format!("{}", Wrapper("a---")), "a---");
+        assert_eq!(format!("{}", Wrapper("a\nb")), r#""a\nb""#);
+        assert_eq!(format!("{}", Wrapper("\"\t\\")), r#""\"\t\\""#);
+    }
+
+    #[test]
+    fn collect_str() {
+        struct Ip([u8; 4]);
+
+        impl core::fmt::Display for Ip {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                let [a, b, c, d] = self.0;
+                write!(f, "{}.{}.{}.{}", a, b, c, d)
+            }
+        }
+
+        impl serde::Serialize for Ip {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        assert_eq!(format!("{}", Wrapper(format_args!("{}", 1))), "'1'");
+        assert_eq!(format!("{}", Wrapper(format_args!("{}: {}", 'a', 'b'))), "'a: b'");
+        assert_eq!(
+            format!("{}", Wrapper(Map(&[("gateway", Ip([10, 0, 0, 1]))]))),
+            "gateway: 10.0.0.1"
+        );
+        assert_eq!(format!("{}", Wrapper(Map(&[(Ip([10, 0, 0, 2]), "up")]))), "10.0.0.2: up");
+        // Too long for the stack buffer, streamed double-quoted
+        let expected = format!(r#""{}\\\"""#, "a".repeat(200));
+        assert_eq!(format!("{}", Wrapper(format_args!(r#"{:a<200}\""#, ""))), expected);
+        let config = Config::new().ascii_only(true);
+        assert_eq!(
+            format!("{}", WithConfig(format_args!("{:ä<200}", ""), config)),
+            format!("\"{}\"", "\\u00E4".repeat(200))
+        );
+    }
+
+    #[test]
+    fn struct_key_quoting() {
+        #[derive(Serialize)]
+        struct Keys {
+            #[serde(rename = "a: b")]
+            colon: u8,
+            #[serde(rename = "#")]
+            hash: u8,
+            #[serde(rename = "- x")]
+            dash: u8,
+            #[serde(rename = "")]
+            empty: u8,
+        }
+
+        let keys = Keys { colon: 0, hash: 1, dash: 2, empty: 3 };
+        assert_eq!(format!("{}", Wrapper(&keys)), "'a: b': 0\n'#': 1\n'- x': 2\n'': 3");
+
+        #[derive(Serialize)]
+        enum Variant {
+            #[serde(rename = "a: b")]
+            Colon { x: u8 },
+            #[serde(rename = "- x")]
+            Dash(u8),
+        }
+
+        assert_eq!(format!("{}", Wrapper(Variant::Colon { x: 0 })), "'a: b':\n  x: 0");
+        assert_eq!(format!("{}", Wrapper(Variant::Dash(0))), "'- x': 0");
+
+        assert_eq!(format!("{}", Wrapper(Map(&[("a: b", 0), ("", 1)]))), "'a: b': 0\n'': 1");
+    }
+
+    #[test]
+    #[cfg(not(feature = "float"))]
+    fn struct_f32_unsupported() {
+        #[derive(Serialize)]
+        struct Temperature {
+            temperature: f32,
+        }
+
+        let mut yaml = std::string::String::new();
+        let result = super::to_fmt(&mut yaml, &Temperature { temperature: -20.0 });
+        assert_eq!(result, Err(core::fmt::Error));
+    }
+
     #[test]
     fn struct_bool() {
         #[derive(Serialize)]
@@ -345,6 +923,8 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "float")]
+    #[allow(clippy::excessive_precision)]
     fn struct_f32() {
         #[derive(Serialize)]
         struct Temperature {
@@ -382,10 +962,57 @@ mod tests {
             "description: An ambient temperature sensor"
         );
 
-        // XXX Ideally this should produce "{}"
         assert_eq!(format!("{}", Wrapper(&Property { description: None })), "description: null");
     }
 
+    #[test]
+    fn test_null_style() {
+        #[derive(Serialize)]
+        struct A {
+            a: Option<u8>,
+            b: [(); 2],
+        }
+
+        let a = A { a: None, b: [(), ()] };
+        let config = Config::new().null_style(NullStyle::Tilde);
+        assert_eq!(format!("{}", WithConfig(&a, config)), "a: ~\nb:\n  - ~\n  - ~");
+        let config = Config::new().null_style(NullStyle::Empty);
+        assert_eq!(format!("{}", WithConfig(&a, config)), "a:\nb:\n  -\n  -");
+        assert_eq!(format!("{}", WithConfig((), config)), "");
+
+        let map = Map(&[(None, 0), (Some(1), 1)]);
+        assert_eq!(format!("{}", WithConfig(map, config)), "~: 0\n1: 1");
+    }
+
+    #[test]
+    fn test_omit_null() {
+        #[derive(Serialize)]
+        struct Property<'a> {
+            description: Option<&'a str>,
+            unit: (),
+            value: Option<Option<u8>>,
+        }
+
+        let config = Config::new().omit_null(true);
+        let property = Property { description: None, unit: (), value: Some(None) };
+        assert_eq!(format!("{}", WithConfig(&property, config)), "{}");
+        let property = Property { description: Some("a"), unit: (), value: Some(Some(1)) };
+        assert_eq!(format!("{}", WithConfig(&property, config)), "description: a\nvalue: 1");
+
+        let map = Map(&[("a", None), ("b", Some(1)), ("c", None)]);
+        assert_eq!(format!("{}", WithConfig(map, config)), "b: 1");
+        assert_eq!(format!("{}", WithConfig([None, Some(1)], config)), "- null\n- 1");
+
+        #[derive(Serialize)]
+        enum A {
+            A(Option<u8>),
+            B { x: Option<u8>, y: u8 },
+        }
+
+        assert_eq!(format!("{}", WithConfig(A::A(None), config)), "A: null");
+        assert_eq!(format!("{}", WithConfig(A::B { x: None, y: 1 }, config)), "B:\n  y: 1");
+    }
+
     #[test]
     fn struct_u8() {
         #[derive(Serialize)]
@@ -449,72 +1076,1371 @@ mod tests {
     }
 
     #[test]
-    fn test_serialize_bytes() {
-        pub struct SimpleDecimal(f32);
-
-        impl serde::Serialize for SimpleDecimal {
-            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-                let string = format!("{:.2}", self.0);
-                serializer.serialize_bytes(string.as_bytes())
-            }
-        }
+    fn test_tuple() {
+        #[derive(Serialize)]
+        struct Point(u8, u8);
 
-        let sd1 = SimpleDecimal(1.55555);
-        assert_eq!(format!("{}", Wrapper(&sd1)), "1.56");
+        assert_eq!(format!("{}", Wrapper(Point(1, 2))), "- 1\n- 2");
 
-        let sd2 = SimpleDecimal(0.000);
-        assert_eq!(format!("{}", Wrapper(&sd2)), "0.00");
+        #[derive(Serialize)]
+        enum A {
+            A(u8, u8),
+        }
 
-        let sd3 = SimpleDecimal(22222.777777);
-        assert_eq!(format!("{}", Wrapper(&sd3)), "22222.78");
+        assert_eq!(format!("{}", Wrapper(A::A(1, 2))), "A:\n  - 1\n  - 2");
+        assert_eq!(format!("{}", Wrapper([A::A(1, 2)])), "- A:\n    - 1\n    - 2");
     }
 
     #[test]
-    fn test_serializable_key() {
-        use std::fmt::Write;
-        use std::string::String;
+    fn test_enum_tag() {
+        #[derive(Serialize)]
+        enum Action {
+            Off,
+            Set(u8),
+            Move { x: u8, y: u8 },
+            Line(u8, u8),
+            Nested(Point),
+        }
 
-        use serde::ser::SerializeMap;
+        #[derive(Serialize)]
+        struct Point {
+            x: u8,
+        }
 
-        struct A(usize);
+        let config = Config::new().enum_style(EnumStyle::Tag);
+        assert_eq!(format!("{}", WithConfig(Action::Off, config)), "!Off");
+        assert_eq!(format!("{}", WithConfig(Action::Set(1), config)), "!Set 1");
+        let action = Action::Move { x: 1, y: 2 };
+        assert_eq!(format!("{}", WithConfig(action, config)), "!Move\nx: 1\ny: 2");
+        assert_eq!(format!("{}", WithConfig(Action::Line(1, 2), config)), "!Line\n- 1\n- 2");
+        let action = Action::Nested(Point { x: 1 });
+        assert_eq!(format!("{}", WithConfig(action, config)), "!Nested\nx: 1");
 
-        impl serde::Serialize for A {
-            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-                let mut string = String::new();
-                write!(string, "A{}", self.0).ok();
-                serializer.serialize_str(string.as_str())
-            }
+        #[derive(Serialize)]
+        struct Step {
+            action: Action,
+            next: Action,
         }
 
-        struct B {
-            a: A,
-            b: usize,
+        let step = Step { action: Action::Move { x: 1, y: 2 }, next: Action::Off };
+        let expected = "action: !Move\n  x: 1\n  y: 2\nnext: !Off";
+        assert_eq!(format!("{}", WithConfig(step, config)), expected);
+
+        let actions = [Action::Line(1, 2), Action::Set(3), Action::Off];
+        let expected = "- !Line\n  - 1\n  - 2\n- !Set 3\n- !Off";
+        assert_eq!(format!("{}", WithConfig(actions, config)), expected);
+
+        let map = Map(&[(Action::Off, 0)]);
+        assert_eq!(format!("{}", WithConfig(map, config)), "'Off': 0");
+    }
+
+    #[test]
+    fn test_anchor() {
+        #[derive(Serialize)]
+        struct Point {
+            x: u8,
+            y: u8,
         }
 
-        impl serde::Serialize for B {
-            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-                let mut map = serializer.serialize_map(Some(1))?;
-                map.serialize_entry(&self.a, &self.b)?;
-                map.end()
-            }
+        let base = Point { x: 1, y: 2 };
+        let points = [Anchored::new("base", &base), Anchored::new("base", &base)];
+        assert_eq!(format!("{}", Wrapper(points)), "- &base\n  x: 1\n  y: 2\n- *base");
+
+        #[derive(Serialize)]
+        struct Shapes<'a> {
+            a: Anchored<&'a Point>,
+            b: Anchored<&'a Point>,
+            c: Anchored<u8>,
+            d: [Anchored<u8>; 2],
         }
 
-        assert_eq!(format!("{}", Wrapper(B { a: A(1), b: 2 })), "A1: 2")
+        let c = Anchored::new("c", 3);
+        let shapes =
+            Shapes { a: Anchored::new("p", &base), b: Anchored::new("p", &base), c, d: [c; 2] };
+        let expected = "a: &p\n  x: 1\n  y: 2\nb: *p\nc: &c 3\nd:\n  - *c\n  - *c";
+        assert_eq!(format!("{}", Wrapper(shapes)), expected);
+
+        for name in ["my anchor", "a,b", "[a]", "{a}", "a\tb", ""] {
+            let mut yaml = std::string::String::new();
+            let result = super::to_fmt(&mut yaml, &[Anchored::new(name, 1)]);
+            assert_eq!(result, Err(core::fmt::Error), "{:?}", name);
+        }
+        assert_eq!(format!("{}", Wrapper(Anchored::new("a-b_c.1", 1))), "&a-b_c.1 1");
     }
 
+    #[cfg(feature = "alloc")]
     #[test]
-    fn test_nested_array() {
+    fn test_auto_anchors() {
         #[derive(Serialize)]
-        struct A {
-            a: [usize; 2],
+        struct Point {
+            x: u8,
+            y: u8,
         }
 
-        assert_eq!(format!("{}", Wrapper(A { a: [0, 1] })), "a:\n  - 0\n  - 1");
+        #[derive(Serialize)]
+        struct Shapes {
+            a: [u8; 2],
+            b: Point,
+            c: [Point; 2],
+            d: [u8; 2],
+            e: u8,
+            f: u8,
+        }
 
-        let array: [[usize; 2]; 2] = [[0, 1], [2, 3]];
+        let p = || Point { x: 1, y: 2 };
+        let shapes = Shapes { a: [1, 2], b: p(), c: [p(), p()], d: [1, 2], e: 1, f: 1 };
+        let config = Config::new().auto_anchors(true);
+        let expected = concat!(
+            "a: &id001\n  - 1\n  - 2\n",
+            "b: &id002\n  x: 1\n  y: 2\n",
+            "c:\n  - *id002\n  - *id002\n",
+            "d: *id001\ne: 1\nf: 1"
+        );
+        assert_eq!(format!("{}", WithConfig(shapes, config)), expected);
+
+        #[derive(Serialize)]
+        struct Outer {
+            p: Point,
+        }
+
+        let outers = [Outer { p: p() }, Outer { p: p() }];
+        let expected = "- &id001\n  p:\n    x: 1\n    y: 2\n- *id001";
+        assert_eq!(format!("{}", WithConfig(outers, config)), expected);
+    }
+
+    #[test]
+    fn test_comment() {
+        #[derive(Serialize)]
+        struct Uart {
+            baudrate: Commented<u32>,
+            parity: Commented<bool>,
+            pins: Commented<[u8; 2]>,
+        }
+
+        #[derive(Serialize)]
+        struct Board {
+            uart: Commented<Uart>,
+            leds: [Commented<bool>; 2],
+        }
+
+        let board = Board {
+            uart: Commented::new(
+                "Debug port",
+                Uart {
+                    baudrate: Commented::new("Bits per second", 115200),
+                    parity: Commented::trailing("Even", false),
+                    pins: Commented::trailing("TX, RX", [1, 2]),
+                },
+            ),
+            leds: [
+                Commented::new("Red\n\nBlinks on error", true),
+                Commented::trailing("Green", false),
+            ],
+        };
+        let expected = concat!(
+            "# Debug port\n",
+            "uart:\n",
+            "  # Bits per second\n",
+            "  baudrate: 115200\n",
+            "  parity: false # Even\n",
+            "  pins: # TX, RX\n",
+            "    - 1\n",
+            "    - 2\n",
+            "leds:\n",
+            "  # Red\n",
+            "  #\n",
+            "  # Blinks on error\n",
+            "  - true\n",
+            "  - false # Green"
+        );
+        assert_eq!(format!("{}", Wrapper(&board)), expected);
+
+        let value = Map(&[("a", Commented::new("A", 1)), ("b", Commented::trailing("B", 2))]);
+        assert_eq!(format!("{}", Wrapper(&value)), "# A\na: 1\nb: 2 # B");
+        assert_eq!(format!("{}", Wrapper([Commented::trailing("A", [1])])), "- # A\n  - 1");
+        assert_eq!(format!("{}", Wrapper(Commented::new("A", 1))), "# A\n1");
+
+        let config = Config::new().header("Generated\nDo not edit");
+        let expected = "# Generated\n# Do not edit\n# A\na: 1\nb: 2 # B";
+        assert_eq!(format!("{}", WithConfig(value, config)), expected);
+
+        // Carriage returns break lines as well, other control characters are escaped
+        let value =
+            Map(&[("a", Commented::new("A\rB", 1)), ("b", Commented::trailing("C\r\nD\x07", 2))]);
+        let config = Config::new().header("Generated\r\rDo not edit\r\n");
+        let expected = "# Generated\n#\n# Do not edit\n# A\n# B\na: 1\nb: 2 # C\n  # D\\x07";
+        assert_eq!(format!("{}", WithConfig(value, config)), expected);
+    }
+
+    #[test]
+    fn test_raw_yaml() {
+        #[derive(Serialize)]
+        struct Config<'a> {
+            network: RawYaml<'a>,
+            pins: RawYaml<'a>,
+            mtu: RawYaml<'a>,
+            text: RawYaml<'a>,
+            empty: RawYaml<'a>,
+        }
+
+        let config = Config {
+            network: RawYaml("\n    wifi:\n      ssid: home\n\n    dhcp: true\n"),
+            pins: RawYaml("- 1\n- 2"),
+            mtu: RawYaml("1500"),
+            text: RawYaml("|\n  line"),
+            empty: RawYaml(""),
+        };
+        let expected = concat!(
+            "network:\n  wifi:\n    ssid: home\n\n  dhcp: true\n",
+            "pins:\n  - 1\n  - 2\n",
+            "mtu: 1500\n",
+            "text: |\n    line\n",
+            "empty: null"
+        );
+        assert_eq!(format!("{}", Wrapper(&config)), expected);
+
+        // Trailing spaces and final line breaks of a keep block scalar are kept
+        let block = RawYaml("key: |+\n  a  \n\n");
+        let expected = "block:\n  key: |+\n    a  \n\n";
+        assert_eq!(format!("{}", Wrapper(Map(&[("block", block)]))), expected);
+        let expected = "block:\n  key: |+\n    a  \n\nnext: [1]";
+        let value = Map(&[("block", block), ("next", RawYaml("[1]"))]);
+        assert_eq!(format!("{}", Wrapper(&value)), expected);
+        let expected = "- block:\n    key: |+\n      a  \n\n  next: [1]";
+        assert_eq!(format!("{}", Wrapper([&value])), expected);
+        let mut yaml = std::string::String::new();
+        let config = super::Config::new().flow(true);
+        assert_eq!(super::to_fmt_with_config(&mut yaml, &[block], config), Err(core::fmt::Error));
+
+        let list = [RawYaml("a: 1\nb: 2"), RawYaml("[1, 2]")];
+        assert_eq!(format!("{}", Wrapper(list)), "- a: 1\n  b: 2\n- [1, 2]");
+        assert_eq!(format!("{}", Wrapper(Commented::trailing("A", RawYaml("a: 1")))), "# A\na: 1");
+    }
+
+    #[test]
+    fn test_with_scalar() {
+        use crate::with;
+
+        #[derive(Serialize)]
+        struct Text<'a> {
+            #[serde(serialize_with = "with::literal")]
+            literal: &'a str,
+            #[serde(serialize_with = "with::folded")]
+            folded: &'a str,
+            #[serde(serialize_with = "with::double_quoted")]
+            quoted: &'a str,
+            #[serde(serialize_with = "with::double_quoted")]
+            char: char,
+            #[serde(serialize_with = "with::hex")]
+            hex: i16,
+            plain: &'a str,
+        }
+
+        let text = Text {
+            literal: "a\n b\n\n",
+            folded: "a\nb\n\nc\n",
+            quoted: "a",
+            char: 'b',
+            hex: -0x1F,
+            plain: "c",
+        };
+        let expected = concat!(
+            "literal: |+\n  a\n   b\n\n",
+            "folded: >\n  a\n\n  b\n\n\n  c\n",
+            "quoted: \"a\"\n",
+            "char: \"b\"\n",
+            "hex: -0x1F\n",
+            "plain: c"
+        );
+        assert_eq!(format!("{}", Wrapper(&text)), expected);
+
+        let words = "word ".repeat(20);
+        let text = Text { folded: words.trim_end(), literal: " a", ..text };
+        let expected = concat!(
+            "literal: \" a\"\n",
+            "folded: >-\n",
+            "  word word word word word word word word word word word word word word word word\n",
+            "  word word word word\n"
+        );
+        assert!(format!("{}", Wrapper(&text)).starts_with(expected));
+
+        #[derive(Serialize)]
+        struct Nested<'a> {
+            #[serde(serialize_with = "with::literal")]
+            literal: &'a str,
+        }
+
+        let nested = [Nested { literal: "a\nb" }];
+        assert_eq!(format!("{}", Wrapper(nested)), "- literal: |-\n    a\n    b");
+    }
+
+    #[test]
+    fn test_with_flow() {
+        use crate::with;
+
+        #[derive(Serialize)]
+        struct Point {
+            x: u8,
+            y: u8,
+        }
+
+        #[derive(Serialize)]
+        enum Shape {
+            Line(Point, Point),
+            Circle { radius: u8 },
+            Dot(Point),
+        }
+
+        #[derive(Serialize)]
+        struct Canvas<'a> {
+            #[serde(serialize_with = "with::flow")]
+            origin: Point,
+            #[serde(serialize_with = "with::flow")]
+            shapes: &'a [Shape],
+            #[serde(serialize_with = "with::flow")]
+            labels: Map<'a, &'a str, Option<&'a str>>,
+            #[serde(serialize_with = "with::flow")]
+            empty: [u8; 0],
+            size: [u8; 1],
+        }
+
+        let shapes = [
+            Shape::Line(Point { x: 0, y: 0 }, Point { x: 1, y: 1 }),
+            Shape::Circle { radius: 2 },
+            Shape::Dot(Point { x: 3, y: 4 }),
+        ];
+        let canvas = Canvas {
+            origin: Point { x: 0, y: 1 },
+            shapes: &shapes,
+            labels: Map(&[("a, b", Some("[c]")), ("d", None)]),
+            empty: [],
+            size: [1],
+        };
+        let expected = concat!(
+            "origin: {x: 0, y: 1}\n",
+            "shapes: [{Line: [{x: 0, y: 0}, {x: 1, y: 1}]}, ",
+            "{Circle: {radius: 2}}, {Dot: {x: 3, y: 4}}]\n",
+            "labels: {'a, b': '[c]', d: null}\n",
+            "empty: []\n",
+            "size:\n  - 1"
+        );
+        assert_eq!(format!("{}", Wrapper(&canvas)), expected);
+
+        let config = Config::new().enum_style(EnumStyle::Tag).null_style(NullStyle::Empty);
+        let expected = concat!(
+            "origin: {x: 0, y: 1}\n",
+            "shapes: [!Line [{x: 0, y: 0}, {x: 1, y: 1}], ",
+            "!Circle {radius: 2}, !Dot {x: 3, y: 4}]\n",
+            "labels: {'a, b': '[c]', d: ~}\n",
+            "empty: []\n",
+            "size:\n  - 1"
+        );
+        assert_eq!(format!("{}", WithConfig(&canvas, config)), expected);
+
+        #[derive(Serialize)]
+        struct Keys<'a> {
+            #[serde(serialize_with = "with::flow")]
+            map: Map<'a, (u8, u8), u8>,
+        }
+
+        let keys = Keys { map: Map(&[((0, 1), 2)]) };
+        assert_eq!(format!("{}", Wrapper(keys)), "map: {[0, 1]: 2}");
+    }
+
+    #[test]
+    fn test_radix() {
+        use crate::with;
+
+        #[derive(Serialize)]
+        struct Register {
+            address: u32,
+            offset: i16,
+            #[serde(serialize_with = "with::decimal")]
+            size: u16,
+            #[serde(serialize_with = "with::octal")]
+            mode: u16,
+            #[serde(serialize_with = "with::binary")]
+            delta: i8,
+            flags: Integer<u8>,
+        }
+
+        let register = Register {
+            address: 0x4000,
+            offset: -2,
+            size: 32,
+            mode: 0o644,
+            delta: -5,
+            flags: Integer::binary(0b101).padded(),
+        };
+        let expected = concat!(
+            "address: 16384\n",
+            "offset: -2\n",
+            "size: 32\n",
+            "mode: 0o644\n",
+            "delta: -0b101\n",
+            "flags: 0b00000101"
+        );
+        assert_eq!(format!("{}", Wrapper(&register)), expected);
+
+        let config = Config::new().unsigned_radix(Radix::Hex);
+        let expected = concat!(
+            "address: 0x4000\n",
+            "offset: -2\n",
+            "size: 32\n",
+            "mode: 0o644\n",
+            "delta: -0b101\n",
+            "flags: 0b00000101"
+        );
+        assert_eq!(format!("{}", WithConfig(&register, config)), expected);
+
+        let config = config.zero_pad(true);
+        let expected = concat!(
+            "address: 0x00004000\n",
+            "offset: -2\n",
+            "size: 32\n",
+            "mode: 0o000644\n",
+            "delta: -0b00000101\n",
+            "flags: 0b00000101"
+        );
+        assert_eq!(format!("{}", WithConfig(&register, config)), expected);
+
+        let config = Config::new().unsigned_radix(Radix::Octal).zero_pad(true);
+        assert_eq!(
+            format!("{}", WithConfig([0u64, 8], config)),
+            "- 0o0000000000000000000000\n- 0o0000000000000000000010"
+        );
+        let config = Config::new().unsigned_radix(Radix::Binary);
+        assert_eq!(format!("{}", WithConfig(Map(&[(5u8, 0u16)]), config)), "0b101: 0b0");
+        let config = Config::new().unsigned_radix(Radix::Hex).quote_large_integers(true);
+        assert_eq!(format!("{}", WithConfig(u64::MAX, config)), "'0xFFFFFFFFFFFFFFFF'");
+    }
+
+    #[test]
+    #[cfg(feature = "float")]
+    #[allow(clippy::excessive_precision)]
+    fn test_float_style() {
+        let values = [0.0, -0.0, 1.0, 21.4999, 0.006, 9.96, -2.3456789012345e-23, 1.5e16, 123.0];
+        let cases = [
+            (
+                FloatStyle::Shortest,
+                "0.0 -0.0 1.0 21.4999 0.006 9.96 -2.3456789012345e-23 1.5e16 123.0",
+            ),
+            (
+                FloatStyle::Plain,
+                concat!(
+                    "0.0 0.0 1.0 21.4999 0.006 9.96 -0.000000000000000000000023456789012345 ",
+                    "15000000000000000.0 123.0"
+                ),
+            ),
+            (
+                FloatStyle::Fixed(2),
+                "0.00 0.00 1.00 21.50 0.01 9.96 0.00 15000000000000000.00 123.00",
+            ),
+            (FloatStyle::Fixed(0), "0 0 1 21 0 10 0 15000000000000000 123"),
+            (FloatStyle::Significant(2), "0.0 0.0 1.0 21.0 0.006 10.0 -2.3e-23 1.5e16 120.0"),
+        ];
+        for (style, expected) in cases {
+            let config = Config::new().float_style(style);
+            let mut formatted = values.map(|v| format!("{}", WithConfig(v, config)));
+            assert_eq!(formatted.join(" "), expected);
+            formatted = values.map(|v| format!("{}", Wrapper(Float::new(style, v))));
+            assert_eq!(formatted.join(" "), expected);
+        }
+
+        // Precision is limited the same way whether it's set by config or by value
+        for (style, expected) in [
+            (FloatStyle::Fixed(30), "1.50000000000000000"),
+            (FloatStyle::Significant(30), "1.5"),
+            (FloatStyle::Fixed(2), "1.50"),
+        ] {
+            let config = Config::new().float_style(style);
+            assert_eq!(format!("{}", WithConfig(1.5, config)), expected);
+            assert_eq!(format!("{}", Wrapper(Float::new(style, 1.5))), expected);
+        }
+        // Half up on the shortest digits, where printf gives 2.67
+        assert_eq!(format!("{}", Wrapper(Float::fixed(2, 2.675))), "2.68");
+
+        let specials = [f64::NAN, f64::INFINITY, f64::NEG_INFINITY];
+        assert_eq!(specials.map(|v| format!("{}", Wrapper(v))).join(" "), ".nan .inf -.inf");
+
+        #[derive(Serialize)]
+        struct Sensor {
+            temperature: f32,
+            voltage: Float<f32>,
+            current: Float<f64>,
+        }
+
+        let sensor = Sensor {
+            temperature: 21.5,
+            voltage: Float::fixed(3, 3.3),
+            current: Float::significant(20, 0.1),
+        };
+        let config = Config::new().float_style(FloatStyle::Fixed(1));
+        let expected = "temperature: 21.5\nvoltage: 3.300\ncurrent: 0.1";
+        assert_eq!(format!("{}", WithConfig(&sensor, config)), expected);
+    }
+
+    #[test]
+    fn test_char_quoting() {
+        for c in (0..0x80u8).map(char::from) {
+            let expected = format!("{}", Wrapper(c.encode_utf8(&mut [0u8; 4])));
+            assert_eq!(format!("{}", Wrapper(c)), expected);
+            assert_eq!(
+                format!("{}", Wrapper(Map(&[(c, c)]))),
+                format!("{}: {}", expected, expected)
+            );
+            assert_eq!(format!("{}", Wrapper([c])), format!("- {}", expected));
+        }
+
+        let punctuation = (0..0x80u8).map(char::from).filter(char::is_ascii_punctuation);
+        let plain: std::string::String = punctuation
+            .clone()
+            .filter(|&c| format!("{}", Wrapper(c)) == format!("{}", c))
+            .collect();
+        assert_eq!(plain, "$()+./;<=\\^_");
+        let quoted = punctuation.filter(|c| !plain.contains(*c)).map(|c| format!("{}", Wrapper(c)));
+        let expected = [
+            "'!'", "'\"'", "'#'", "'%'", "'&'", "''''", "'*'", "','", "'-'", "':'", "'>'", "'?'",
+            "'@'", "'['", "']'", "'`'", "'{'", "'|'", "'}'", "'~'",
+        ];
+        assert!(quoted.eq(expected));
+
+        assert_eq!(format!("{}", Wrapper(' ')), "' '");
+        assert_eq!(format!("{}", Wrapper('\n')), "\"\\n\"");
+        assert_eq!(format!("{}", Wrapper('\t')), "\"\\t\"");
+        assert_eq!(format!("{}", Wrapper('\x7F')), "\"\\x7F\"");
+        assert_eq!(format!("{}", Wrapper(['1', 'a', '.'])), "- '1'\n- a\n- .");
+    }
+
+    #[test]
+    fn test_ascii_only() {
+        use crate::with;
+
+        #[derive(Serialize)]
+        struct Label<'a> {
+            name: &'a str,
+            unit: char,
+            #[serde(serialize_with = "with::literal")]
+            note: &'a str,
+            #[serde(serialize_with = "with::double_quoted")]
+            quoted: &'a str,
+            plain: &'a str,
+            tags: Map<'a, &'a str, &'a str>,
+        }
+
+        let label = Label {
+            name: "Küche",
+            unit: '°',
+            note: "Temperatur\n🌡\n",
+            quoted: "é",
+            plain: "kitchen",
+            tags: Map(&[("größe", "klein")]),
+        };
+        let expected = concat!(
+            "name: Küche\n",
+            "unit: °\n",
+            "note: |\n  Temperatur\n  🌡\n",
+            "quoted: \"é\"\n",
+            "plain: kitchen\n",
+            "tags:\n  größe: klein"
+        );
+        assert_eq!(format!("{}", Wrapper(&label)), expected);
+
+        let config = Config::new().ascii_only(true);
+        let expected = concat!(
+            "name: \"K\\u00FCche\"\n",
+            "unit: \"\\u00B0\"\n",
+            "note: \"Temperatur\\n\\U0001F321\\n\"\n",
+            "quoted: \"\\u00E9\"\n",
+            "plain: kitchen\n",
+            "tags:\n  \"gr\\u00F6\\u00DFe\": klein"
+        );
+        assert_eq!(format!("{}", WithConfig(&label, config)), expected);
+
+        let readings = [Commented::new("Temperatur °", 1), Commented::trailing("Küche 🌡", 2)];
+        let config = config.header("Größe");
+        let expected = concat!(
+            "# Gr\\u00F6\\u00DFe\n",
+            "# Temperatur \\u00B0\n",
+            "- 1\n",
+            "- 2 # K\\u00FCche \\U0001F321"
+        );
+        assert_eq!(format!("{}", WithConfig(readings, config)), expected);
+        let expected = "# Größe\n# Temperatur °\n- 1\n- 2 # Küche 🌡";
+        assert_eq!(format!("{}", WithConfig(readings, config.ascii_only(false))), expected);
+
+        // Anchor names and raw fragments can't be escaped
+        let mut yaml = std::string::String::new();
+        let value = [Anchored::new("größe", 1)];
+        assert_eq!(super::to_fmt_with_config(&mut yaml, &value, config), Err(core::fmt::Error));
+        let value = [RawYaml("größe: klein")];
+        assert_eq!(super::to_fmt_with_config(&mut yaml, &value, config), Err(core::fmt::Error));
+        let value = [Anchored::new("size", RawYaml("small"))];
+        assert_eq!(
+            format!("{}", WithConfig(value, Config::new().ascii_only(true))),
+            "- &size small"
+        );
+    }
+
+    #[test]
+    fn test_line_ending() {
+        use crate::with;
+
+        #[derive(Serialize)]
+        struct Document<'a> {
+            #[serde(serialize_with = "with::literal")]
+            literal: &'a str,
+            #[serde(serialize_with = "with::folded")]
+            folded: &'a str,
+            escaped: &'a str,
+            commented: Commented<u8>,
+            raw: RawYaml<'a>,
+            list: [[u8; 2]; 2],
+            map: Map<'a, (u8, u8), &'a str>,
+        }
+
+        let words = "word ".repeat(20);
+        let document = Document {
+            literal: "a\nb\n\n",
+            folded: words.trim_end(),
+            escaped: "a\nb\r",
+            commented: Commented::new("one\ntwo", 1),
+            raw: RawYaml("x: 1\ny: 2"),
+            list: [[1, 2], [3, 4]],
+            map: Map(&[((1, 2), "a")]),
+        };
+        let config = Config::new().header("header").line_ending(LineEnding::CrLf);
+        let lf = format!("{}", WithConfig(&document, config.line_ending(LineEnding::Lf)));
+        let crlf = format!("{}", WithConfig(&document, config));
+        assert_eq!(lf.matches('\n').count(), 23);
+        assert!(!lf.contains('\r'));
+        assert!(lf.contains("escaped: \"a\\nb\\r\""));
+        assert_eq!(crlf, lf.replace('\n', "\r\n"));
+    }
+
+    #[test]
+    fn test_final_newline() {
+        let config = Config::new().final_newline(true);
+        assert_eq!(format!("{}", WithConfig(1, config)), "1\n");
+        assert_eq!(format!("{}", WithConfig("a", config)), "a\n");
+        assert_eq!(format!("{}", WithConfig([1, 2], config)), "- 1\n- 2\n");
+        assert_eq!(format!("{}", WithConfig(Map(&[("a", 1)]), config)), "a: 1\n");
+        assert_eq!(format!("{}", WithConfig(Map::<u8, u8>(&[]), config)), "{}\n");
+        assert_eq!(format!("{}", WithConfig([[1]], config.header("list"))), "# list\n- - 1\n");
+        let config = config.line_ending(LineEnding::CrLf);
+        assert_eq!(format!("{}", WithConfig(Map(&[("a", [1])]), config)), "a:\r\n  - 1\r\n");
+
+        #[derive(Serialize)]
+        struct Text<'a> {
+            #[serde(serialize_with = "crate::with::literal")]
+            text: &'a str,
+        }
+
+        // A block scalar ends its last line, which the final newline doesn't repeat
+        for config in [Config::new(), Config::new().final_newline(true)] {
+            let text = Text { text: "a\n\n" };
+            assert_eq!(format!("{}", WithConfig(&text, config)), "text: |+\n  a\n\n");
+            let text = Text { text: "a\nb\n" };
+            assert_eq!(format!("{}", WithConfig(&text, config)), "text: |\n  a\n  b\n");
+        }
+        let text = Text { text: "a" };
+        assert_eq!(format!("{}", Wrapper(&text)), "text: |-\n  a");
+        let config = Config::new().final_newline(true);
+        assert_eq!(format!("{}", WithConfig(&text, config)), "text: |-\n  a\n");
+    }
+
+    #[test]
+    fn test_align_values() {
+        #[derive(Serialize)]
+        struct Channel {
+            id: u8,
+            name: &'static str,
+            gain: Option<u8>,
+        }
+
+        #[derive(Serialize)]
+        enum Mode {
+            Sample { rate: u32, oversampling: u8 },
+        }
+
+        #[derive(Serialize)]
+        struct Status<'a> {
+            name: &'a str,
+            voltage: i32,
+            channels: [Channel; 2],
+            labels: Map<'a, &'a str, u8>,
+            mode: Mode,
+            flags: Anchored<u8>,
+            description: Option<&'a str>,
+        }
+
+        let status = Status {
+            name: "foo",
+            voltage: 33,
+            channels: [
+                Channel { id: 1, name: "left", gain: None },
+                Channel { id: 2, name: "right", gain: Some(3) },
+            ],
+            labels: Map(&[("a", 1), ("long label", 2)]),
+            mode: Mode::Sample { rate: 48000, oversampling: 4 },
+            flags: Anchored::new("flags", 0),
+            description: None,
+        };
+        let config = Config::new().align_values(true);
+        let expected = concat!(
+            "name:        foo\n",
+            "voltage:     33\n",
+            "channels:\n",
+            "  - id:   1\n",
+            "    name: left\n",
+            "    gain: null\n",
+            "  - id:   2\n",
+            "    name: right\n",
+            "    gain: 3\n",
+            "labels:\n",
+            "  a:          1\n",
+            "  long label: 2\n",
+            "mode:\n",
+            "  Sample:\n",
+            "    rate:         48000\n",
+            "    oversampling: 4\n",
+            "flags:       &flags 0\n",
+            "description: null"
+        );
+        assert_eq!(format!("{}", WithConfig(&status, config)), expected);
+
+        let config = config.omit_null(true).null_style(NullStyle::Empty);
+        let expected = concat!(
+            "name:     foo\n",
+            "voltage:  33\n",
+            "channels:\n",
+            "  - id:   1\n",
+            "    name: left\n",
+            "  - id:   2\n",
+            "    name: right\n",
+            "    gain: 3\n",
+        );
+        assert!(format!("{}", WithConfig(&status, config)).starts_with(expected));
+
+        let keys: [(u16, u8); 20] = core::array::from_fn(|i| (i as u16, 0));
+        let keys = [&keys[..19], &[(10000, 0)]].concat();
+        let expected = "0:  0\n1:  0\n";
+        assert!(format!("{}", WithConfig(Map(&keys), config)).starts_with(expected));
+        let expected = "15: 0\n16: 0\n17: 0\n18: 0\n10000: 0";
+        assert!(format!("{}", WithConfig(Map(&keys), config)).ends_with(expected));
+
+        #[derive(Serialize)]
+        struct Flow<'a> {
+            #[serde(serialize_with = "crate::with::flow")]
+            map: Map<'a, &'a str, u8>,
+        }
+        let map = Map(&[("a", 0), ("bb", 1)]);
+        assert_eq!(format!("{}", WithConfig([&map], config)), "- a:  0\n  bb: 1");
+        assert_eq!(format!("{}", WithConfig(Flow { map }, config)), "map: {a: 0, bb: 1}");
+    }
+
+    #[test]
+    fn test_yaml_display() {
+        use crate::with;
+
+        #[derive(Serialize)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        #[derive(Serialize)]
+        struct Shape<'a> {
+            points: [Point; 2],
+            nested: [[u8; 2]; 2],
+            #[serde(serialize_with = "with::literal")]
+            text: &'a str,
+            raw: RawYaml<'a>,
+            labels: Map<'a, (u8, u8), &'a str>,
+        }
+
+        let shape = Shape {
+            points: [Point { x: 0, y: 0 }, Point { x: 1, y: 2 }],
+            nested: [[1, 2], [3, 4]],
+            text: "a\nb",
+            raw: RawYaml("a: 1\nb: 2"),
+            labels: Map(&[((1, 2), "a")]),
+        };
+        let expected = concat!(
+            "points:\n",
+            "    -   x: 0\n",
+            "        y: 0\n",
+            "    -   x: 1\n",
+            "        y: 2\n",
+            "nested:\n",
+            "    -   - 1\n",
+            "        - 2\n",
+            "    -   - 3\n",
+            "        - 4\n",
+            "text: |-\n",
+            "    a\n",
+            "    b\n",
+            "raw:\n",
+            "    a: 1\n",
+            "    b: 2\n",
+            "labels:\n",
+            "    ?   - 1\n",
+            "        - 2\n",
+            "    : a"
+        );
+        assert_eq!(format!("{:4}", Yaml::new(&shape)), expected);
+        assert_eq!(format!("{:1}", Yaml::new(&shape)), format!("{}", Yaml::new(&shape)));
+
+        let shape = Shape { raw: RawYaml("{a: 1, b: 2}"), ..shape };
+        let expected = concat!(
+            "{points: [{x: 0, y: 0}, {x: 1, y: 2}], nested: [[1, 2], [3, 4]], ",
+            "text: \"a\\nb\", raw: {a: 1, b: 2}, labels: {[1, 2]: a}}"
+        );
+        assert_eq!(format!("{:#}", Yaml::new(&shape)), expected);
+        let mut yaml = std::string::String::new();
+        let config = Config::new().flow(true);
+        let result = super::to_fmt_with_config(&mut yaml, &[RawYaml("a: 1")], config);
+        assert_eq!(result, Err(core::fmt::Error));
+        assert_eq!(format!("{:#}", Yaml::new([1, 2])), "[1, 2]");
+        assert_eq!(format!("{:#}", Yaml::new("a, b")), "a, b");
+
+        let config = Config::new().header("points").final_newline(true);
+        let expected = "# points\n---\n{x: 1, y: 2}\n...\n";
+        assert_eq!(format!("{:+#}", Yaml::with_config(&shape.points[1], config)), expected);
+        assert_eq!(format!("{:+}", Yaml::new(1)), "---\n1\n...");
+    }
+
+    #[test]
+    #[cfg(feature = "defmt")]
+    fn test_defmt_format() {
+        // Logging needs a global logger, which only exists on target
+        fn format<T: defmt::Format>(_value: &T) {}
+        format(&Yaml::new(Map(&[("a", [1, 2])])));
+    }
+
+    #[test]
+    fn test_emitter() {
+        use super::Emitter;
+
+        fn emit(config: Config) -> Result<std::string::String, core::fmt::Error> {
+            let mut emitter = Emitter::with_config(std::string::String::new(), config);
+            emitter.document_start()?;
+            emitter.begin_mapping()?;
+            emitter.key("registers")?;
+            emitter.begin_sequence()?;
+            for (name, value) in [("ctrl", "0x01"), ("status", "ready: yes")] {
+                emitter.comment(name)?;
+                emitter.begin_mapping()?;
+                emitter.key("name")?;
+                emitter.scalar(name)?;
+                emitter.key("value")?;
+                if name == "ctrl" {
+                    emitter.plain(value)?;
+                } else {
+                    emitter.scalar(value)?;
+                }
+                emitter.key("reset")?;
+                emitter.null()?;
+                emitter.end()?;
+            }
+            emitter.begin_sequence()?;
+            emitter.scalar("a")?;
+            emitter.scalar("true")?;
+            emitter.end()?;
+            emitter.end()?;
+            emitter.comment("empty")?;
+            emitter.key("empty")?;
+            emitter.begin_mapping()?;
+            emitter.end()?;
+            emitter.key("none")?;
+            emitter.begin_sequence()?;
+            emitter.end()?;
+            emitter.end()?;
+            Ok(emitter.into_inner())
+        }
+
+        let expected = concat!(
+            "---\n",
+            "registers:\n",
+            "  # ctrl\n",
+            "  - name: ctrl\n",
+            "    value: 0x01\n",
+            "    reset: null\n",
+            "  # status\n",
+            "  - name: status\n",
+            "    value: 'ready: yes'\n",
+            "    reset: null\n",
+            "  - - a\n",
+            "    - 'true'\n",
+            "# empty\n",
+            "empty: {}\n",
+            "none: []"
+        );
+        assert_eq!(emit(Config::new()).unwrap(), expected);
+        let expected = concat!(
+            "---\n",
+            "{registers: [{name: ctrl, value: 0x01, reset: null}, ",
+            "{name: status, value: 'ready: yes', reset: null}, ",
+            "[a, 'true']], empty: {}, none: []}"
+        );
+        assert_eq!(emit(Config::new().flow(true)).unwrap(), expected);
+
+        let mut emitter = Emitter::new(std::string::String::new());
+        assert_eq!(emitter.key("a"), Err(core::fmt::Error));
+        assert_eq!(emitter.end(), Err(core::fmt::Error));
+        emitter.scalar("a: b").unwrap();
+        assert_eq!(emitter.scalar("c"), Err(core::fmt::Error));
+        assert_eq!(emitter.begin_mapping(), Err(core::fmt::Error));
+        assert_eq!(emitter.into_inner(), "'a: b'");
+
+        let mut emitter = Emitter::new(std::string::String::new());
+        emitter.begin_mapping().unwrap();
+        assert_eq!(emitter.scalar("x"), Err(core::fmt::Error));
+        assert_eq!(emitter.plain("x"), Err(core::fmt::Error));
+        assert_eq!(emitter.null(), Err(core::fmt::Error));
+        assert_eq!(emitter.begin_sequence(), Err(core::fmt::Error));
+        emitter.key("k").unwrap();
+        assert_eq!(emitter.key("k2"), Err(core::fmt::Error));
+        assert_eq!(emitter.end(), Err(core::fmt::Error));
+        assert_eq!(emitter.plain("a\nb"), Err(core::fmt::Error));
+        emitter.plain("1").unwrap();
+        emitter.end().unwrap();
+        assert_eq!(emitter.end(), Err(core::fmt::Error));
+        emitter.document_start().unwrap();
+        emitter.plain("2").unwrap();
+        assert_eq!(emitter.into_inner(), "k: 1\n---\n2");
+    }
+
+    #[test]
+    fn test_seq_writer() {
+        #[derive(Serialize)]
+        struct Sample {
+            id: u32,
+            flags: [u8; 2],
+        }
+
+        let samples = [Sample { id: 1, flags: [0, 1] }, Sample { id: 2, flags: [1, 0] }];
+        let configs = [
+            Config::new(),
+            Config::new().flow(true),
+            Config::new().header("telemetry").document_markers(true).final_newline(true),
+        ];
+        for config in configs {
+            let mut writer = SeqWriter::with_config(std::string::String::new(), config);
+            for sample in &samples {
+                writer.push(sample).unwrap();
+            }
+            let expected = format!("{}", WithConfig(&samples, config));
+            assert_eq!(writer.finish().unwrap(), expected);
+            let empty: [u8; 0] = [];
+            let writer = SeqWriter::with_config(std::string::String::new(), config);
+            assert_eq!(writer.finish().unwrap(), format!("{}", WithConfig(empty, config)));
+        }
+
+        let mut writer = SeqWriter::new(std::string::String::new());
+        writer.push(&Commented::new("first", 1)).unwrap();
+        writer.push(&[2, 3]).unwrap();
+        assert_eq!(writer.finish().unwrap(), "# first\n- 1\n- - 2\n  - 3");
+    }
+
+    #[test]
+    fn test_serialize_iter() {
+        #[derive(Serialize)]
+        struct Log<'a> {
+            #[serde(rename = "error count")]
+            count: usize,
+            errors: Commented<SerializeIter<core::iter::Filter<core::slice::Iter<'a, u16>, Zero>>>,
+            last: SerializeIter<core::iter::Skip<core::slice::Iter<'a, u16>>>,
+        }
+
+        type Zero = fn(&&u16) -> bool;
+
+        let readings = [310, 0, 295, 0, 301];
+        let zero: Zero = |&&r| r == 0;
+        let errors = SerializeIter::new(readings.iter().filter(zero)).flow_up_to(4);
+        let log = Log {
+            count: 2,
+            errors: Commented::new("zeroed", errors),
+            last: SerializeIter::new(readings.iter().skip(3)).flow_up_to(2),
+        };
+        let config = Config::new().align_values(true);
+        assert_eq!(
+            format!("{}", WithConfig(&log, config)),
+            "error count: 2\n# zeroed\nerrors:\n  - 0\n  - 0\nlast:        [0, 301]"
+        );
+        let empty = SerializeIter::new(readings.iter().take(0));
+        assert_eq!(format!("{}", Wrapper(empty.clone())), "[]");
+        assert_eq!(format!("{}", Wrapper(empty.clone().flow_up_to(0))), "[]");
+        assert_eq!(format!("{}", Wrapper([empty])), "- []");
+    }
+
+    #[test]
+    fn test_to_fmt_at_path() {
+        use super::{to_fmt_at_path, to_fmt_at_path_with_config};
+
+        #[derive(Serialize)]
+        enum Address {
+            Dhcp,
+            Static { ip: &'static str, mask: u8 },
+            Gateway(&'static str),
+            Bond(&'static str, &'static str),
+        }
+
+        #[derive(Serialize)]
+        struct Interface {
+            name: &'static str,
+            address: Address,
+        }
+
+        #[derive(Serialize)]
+        struct Network<'a> {
+            interfaces: [Interface; 4],
+            vlans: Map<'a, u16, Commented<&'static str>>,
+            dns: Option<[&'static str; 2]>,
+        }
+
+        let vlans = [(10, Commented::new("office", "lan")), (20, Commented::new("lab", "dmz"))];
+        let network = Network {
+            interfaces: [
+                Interface { name: "eth0", address: Address::Dhcp },
+                Interface { name: "eth1", address: Address::Static { ip: "10.0.0.2", mask: 24 } },
+                Interface { name: "eth2", address: Address::Gateway("10.0.0.1") },
+                Interface { name: "bond0", address: Address::Bond("eth0", "eth2") },
+            ],
+            vlans: Map(&vlans),
+            dns: Some(["1.1.1.1", "9.9.9.9"]),
+        };
+        let at = |path: &str, config: Config| -> Result<std::string::String, core::fmt::Error> {
+            let mut yaml = std::string::String::new();
+            to_fmt_at_path_with_config(&mut yaml, &network, path, config)?;
+            Ok(yaml)
+        };
+        let config = Config::new();
+        assert_eq!(at("", config), Ok(format!("{}", Wrapper(&network))));
+        assert_eq!(
+            at("interfaces[1]", config).unwrap(),
+            "name: eth1\naddress:\n  Static:\n    ip: 10.0.0.2\n    mask: 24"
+        );
+        assert_eq!(at("interfaces[1].address.Static", config).unwrap(), "ip: 10.0.0.2\nmask: 24");
+        assert_eq!(at("interfaces[3].address.Bond", config).unwrap(), "- eth0\n- eth2");
+        assert_eq!(at("interfaces[3].address.Bond[1]", config).unwrap(), "eth2");
+        assert_eq!(at("interfaces[1].address.Static.mask", config).unwrap(), "24");
+        assert_eq!(at("interfaces[2].address.Gateway", config).unwrap(), "10.0.0.1");
+        assert_eq!(at("interfaces[0].address", config).unwrap(), "Dhcp");
+        assert_eq!(at("vlans.20", config).unwrap(), "# lab\ndmz");
+        assert_eq!(at("dns[1]", config).unwrap(), "9.9.9.9");
+        let config = Config::new().flow(true).document_markers(true);
+        assert_eq!(
+            at("interfaces[1].address.Static", config).unwrap(),
+            "---\n{ip: 10.0.0.2, mask: 24}\n..."
+        );
+        assert_eq!(at("interfaces[3].address.Bond", config).unwrap(), "---\n[eth0, eth2]\n...");
+        let config = Config::new().enum_style(EnumStyle::Tag).document_markers(true);
+        assert_eq!(at("interfaces[1].address.ip", config).unwrap(), "---\n10.0.0.2\n...");
+        assert_eq!(at("interfaces[1].address.Static.ip", config), Err(core::fmt::Error));
+
+        let config = Config::new();
+        for path in ["interfaces[4]", "interfaces.eth0", "vlans.30", "dns[0].name", "mtu"] {
+            assert_eq!(at(path, config), Err(core::fmt::Error), "{}", path);
+        }
+        for path in ["interfaces[x]", "interfaces[0", "interfaces..name", "[0]."] {
+            assert_eq!(at(path, config), Err(core::fmt::Error), "{}", path);
+        }
+        let mut yaml = std::string::String::new();
+        to_fmt_at_path(&mut yaml, &[[1, 2], [3, 4]], "[1][0]").unwrap();
+        assert_eq!(yaml, "3");
+    }
+
+    #[test]
+    fn test_redact() {
+        use super::to_fmt_at_path_with_config;
+
+        #[derive(Serialize)]
+        struct Wifi {
+            ssid: &'static str,
+            psk: &'static str,
+        }
+
+        #[derive(Serialize)]
+        struct Network<'a> {
+            wifi: [Wifi; 2],
+            vpn: Map<'a, &'static str, Map<'a, &'static str, &'static str>>,
+        }
+
+        #[derive(Serialize)]
+        struct Device<'a> {
+            name: &'static str,
+            password: Option<&'static str>,
+            network: Network<'a>,
+        }
+
+        let office = [("user", "dev"), ("key", "s3cr3t")];
+        let lab = [("user", "lab"), ("key", "hunter2")];
+        let device = Device {
+            name: "sensor",
+            password: Some("admin"),
+            network: Network {
+                wifi: [Wifi { ssid: "home", psk: "abc" }, Wifi { ssid: "lab", psk: "def" }],
+                vpn: Map(&[("office", Map(&office)), ("lab", Map(&lab))]),
+            },
+        };
+        let config = Config::new().redact(&["password", "network.wifi.psk", "network.vpn.*.key"]);
+        assert_eq!(
+            format!("{}", WithConfig(&device, config)),
+            concat!(
+                "name: sensor\n",
+                "password: '***'\n",
+                "network:\n",
+                "  wifi:\n",
+                "    - ssid: home\n",
+                "      psk: '***'\n",
+                "    - ssid: lab\n",
+                "      psk: '***'\n",
+                "  vpn:\n",
+                "    office:\n",
+                "      user: dev\n",
+                "      key: '***'\n",
+                "    lab:\n",
+                "      user: lab\n",
+                "      key: '***'"
+            )
+        );
+        assert_eq!(
+            format!("{}", WithConfig(&device.network.wifi, config.flow(true))),
+            "[{ssid: home, psk: abc}, {ssid: lab, psk: def}]"
+        );
+        assert_eq!(
+            format!("{}", WithConfig(&device.network.vpn, Config::new().redact(&["key"]))),
+            "office:\n  user: dev\n  key: '***'\nlab:\n  user: lab\n  key: '***'"
+        );
+        let aligned = Config::new().redact(&["psk"]).align_values(true);
+        assert_eq!(
+            format!("{}", WithConfig(&device.network.wifi[0], aligned)),
+            "ssid: home\npsk:  '***'"
+        );
+        assert_eq!(
+            format!("{}", WithConfig(&device, config.flow(true).redact(&["network"]))),
+            "{name: sensor, password: admin, network: '***'}"
+        );
+
+        let at = |path: &str| -> Result<std::string::String, core::fmt::Error> {
+            let mut yaml = std::string::String::new();
+            let config = Config::new().redact(&["password", "network.wifi.psk"]);
+            to_fmt_at_path_with_config(&mut yaml, &device, path, config)?;
+            Ok(yaml)
+        };
+        assert_eq!(at("network.wifi[1]").unwrap(), "ssid: lab\npsk: '***'");
+        assert_eq!(at("network.wifi[1].psk").unwrap(), "'***'");
+        assert_eq!(at("password").unwrap(), "'***'");
+        assert_eq!(at("network.vpn.lab.key").unwrap(), "hunter2");
+        let config = Config::new().redact(&["network"]);
+        let mut yaml = std::string::String::new();
+        let result = to_fmt_at_path_with_config(&mut yaml, &device, "network.wifi", config);
+        assert_eq!(result, Err(core::fmt::Error));
+
+        let config = Config::new().redact(&["home.psk"]);
+        let mut writer = SeqWriter::with_config(std::string::String::new(), config);
+        writer.push(&Map(&[("home", &device.network.wifi[0])])).unwrap();
+        assert_eq!(writer.finish().unwrap(), "- home:\n    ssid: home\n    psk: '***'");
+
+        // Too many paths are rejected once the config is built
+        const CONFIG: Config = Config::new().redact(&["a.b"; 32]);
+        assert_eq!(format!("{}", WithConfig(1, CONFIG)), "1");
+        let result = std::panic::catch_unwind(|| Config::new().redact(&["a.b"; 33]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_block_scalar_end() {
+        use crate::with;
+
+        struct Literal(&'static str);
+
+        impl serde::Serialize for Literal {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                with::literal(self.0, serializer)
+            }
+        }
+
+        struct Folded(&'static str);
+
+        impl serde::Serialize for Folded {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                with::folded(self.0, serializer)
+            }
+        }
+
+        fn yaml<T: serde::Serialize>(value: T, config: Config) -> std::string::String {
+            format!("{}", WithConfig(value, config))
+        }
+
+        // The last line break of a block scalar is written by the scalar itself,
+        // whether or not the document ends with it
+        for config in [Config::new(), Config::new().final_newline(true)] {
+            assert_eq!(yaml(Literal("a\nb\n"), config), "|\n  a\n  b\n");
+            assert_eq!(yaml(Literal("a\n\n"), config), "|+\n  a\n\n");
+            assert_eq!(yaml(Folded("a\nb\n"), config), ">\n  a\n\n  b\n");
+            assert_eq!(
+                yaml(&[Literal("a\n"), Literal("b\n\n")], config),
+                "- |\n  a\n- |+\n  b\n\n"
+            );
+            assert_eq!(yaml([Folded("a b\n")], config), "- >\n  a b\n");
+            assert_eq!(yaml(Map(&[("a", [Literal("b\n")])]), config), "a:\n  - |\n    b\n");
+        }
+        // Stripped block scalars end where the next line break is written
+        assert_eq!(format!("{}", Wrapper(Literal("a\nb"))), "|-\n  a\n  b");
+        let config = Config::new().final_newline(true).document_markers(true);
+        assert_eq!(format!("{}", WithConfig(Literal("a\n"), config)), "---\n|\n  a\n...\n");
+
+        #[derive(Serialize)]
+        struct Text {
+            a: Commented<Literal>,
+            c: Literal,
+        }
+
+        let text = Text { a: Commented::trailing("end", Literal("b\n")), c: Literal("d") };
+        assert_eq!(format!("{}", Wrapper(text)), "a: |\n  b\n# end\nc: |-\n  d");
+    }
+
+    #[test]
+    #[allow(clippy::excessive_precision)]
+    fn test_serialize_bytes() {
+        pub struct SimpleDecimal(f32);
+
+        impl serde::Serialize for SimpleDecimal {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let string = format!("{:.2}", self.0);
+                serializer.serialize_bytes(string.as_bytes())
+            }
+        }
+
+        let sd1 = SimpleDecimal(1.55555);
+        assert_eq!(format!("{}", Wrapper(&sd1)), "1.56");
+
+        let sd2 = SimpleDecimal(0.000);
+        assert_eq!(format!("{}", Wrapper(&sd2)), "0.00");
+
+        let sd3 = SimpleDecimal(22222.777777);
+        assert_eq!(format!("{}", Wrapper(&sd3)), "22222.78");
+    }
+
+    #[test]
+    fn test_serializable_key() {
+        use std::fmt::Write;
+        use std::string::String;
+
+        use serde::ser::SerializeMap;
+
+        struct A(usize);
+
+        impl serde::Serialize for A {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut string = String::new();
+                write!(string, "A{}", self.0).ok();
+                serializer.serialize_str(string.as_str())
+            }
+        }
+
+        struct B {
+            a: A,
+            b: usize,
+        }
+
+        impl serde::Serialize for B {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(&self.a, &self.b)?;
+                map.end()
+            }
+        }
+
+        assert_eq!(format!("{}", Wrapper(B { a: A(1), b: 2 })), "A1: 2")
+    }
+
+    #[test]
+    fn test_scalar_key() {
+        assert_eq!(format!("{}", Wrapper(Map(&[(1, "a"), (-2, "b")]))), "1: a\n-2: b");
+        assert_eq!(format!("{}", Wrapper(Map(&[(true, 1), (false, 0)]))), "true: 1\nfalse: 0");
+
+        #[derive(Serialize)]
+        enum Channel {
+            Red,
+            #[serde(rename = "- green")]
+            Green,
+        }
+
+        let map = Map(&[(Channel::Red, 1), (Channel::Green, 2)]);
+        assert_eq!(format!("{}", Wrapper(map)), "Red: 1\n'- green': 2");
+    }
+
+    #[test]
+    fn test_complex_key() {
+        let map = Map(&[((0u8, 1u8), "a"), ((1, 0), "b")]);
+        assert_eq!(format!("{}", Wrapper(map)), "? - 0\n  - 1\n: a\n? - 1\n  - 0\n: b");
+
+        #[derive(Serialize)]
+        struct Point {
+            x: u8,
+            y: u8,
+        }
+
+        let map = Map(&[(Point { x: 0, y: 1 }, [1, 2])]);
+        assert_eq!(format!("{}", Wrapper(map)), "? x: 0\n  y: 1\n:\n  - 1\n  - 2");
+
+        #[derive(Serialize)]
+        struct Nested<'a> {
+            map: Map<'a, [u8; 0], u8>,
+        }
+
+        let nested = Nested { map: Map(&[([], 0)]) };
+        assert_eq!(format!("{}", Wrapper(nested)), "map:\n  ? []\n  : 0");
+    }
+
+    #[test]
+    fn test_empty_nested() {
+        #[derive(Serialize)]
+        struct Empty {}
+
+        #[derive(Serialize)]
+        struct A {
+            a: [u8; 0],
+            b: Empty,
+        }
+
+        assert_eq!(format!("{}", Wrapper(A { a: [], b: Empty {} })), "a: []\nb: {}");
+        assert_eq!(format!("{}", Wrapper([[0u8; 0]; 2])), "- []\n- []");
+    }
+
+    #[test]
+    fn test_nested_array() {
+        #[derive(Serialize)]
+        struct A {
+            a: [usize; 2],
+        }
+
+        assert_eq!(format!("{}", Wrapper(A { a: [0, 1] })), "a:\n  - 0\n  - 1");
+
+        let array: [[usize; 2]; 2] = [[0, 1], [2, 3]];
         assert_eq!(format!("{}", Wrapper(array)), "- - 0\n  - 1\n- - 2\n  - 3");
     }
 
+    #[test]
+    fn test_i128() {
+        assert_eq!(format!("{}", Wrapper(u128::MAX)), "340282366920938463463374607431768211455");
+        assert_eq!(format!("{}", Wrapper(i128::MIN)), "-170141183460469231731687303715884105728");
+    }
+
+    #[test]
+    fn test_quote_large_integers() {
+        let config = Config::new().quote_large_integers(true);
+        assert_eq!(format!("{}", WithConfig(1u64 << 53, config)), "9007199254740992");
+        assert_eq!(format!("{}", WithConfig((1u64 << 53) + 1, config)), "'9007199254740993'");
+        assert_eq!(format!("{}", WithConfig(-(1i64 << 53) - 1, config)), "'-9007199254740993'");
+        let expected = "'340282366920938463463374607431768211455'";
+        assert_eq!(format!("{}", WithConfig(u128::MAX, config)), expected);
+        assert_eq!(format!("{}", WithConfig([1u32, 2], config)), "- 1\n- 2");
+    }
+
     #[test]
     fn test_nested_struct() {
         #[derive(Serialize)]
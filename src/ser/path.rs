@@ -0,0 +1,685 @@
+use core::fmt;
+
+use serde::ser;
+
+use crate::ser::redact::{Redaction, PLACEHOLDER};
+use crate::ser::{document, sequence, struct_, Config, EnumStyle, Serializer};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Segment<'p> {
+    Key(&'p str),
+    Index(usize),
+}
+
+/// First segment of the path and the rest, none once the path is exhausted
+fn split(path: &str) -> Result<Option<(Segment<'_>, &str)>, fmt::Error> {
+    if path.is_empty() {
+        return Ok(None);
+    }
+    if let Some(rest) = path.strip_prefix('[') {
+        let end = rest.find(']').ok_or(fmt::Error)?;
+        let index = rest[..end].parse().map_err(|_| fmt::Error)?;
+        return Ok(Some((Segment::Index(index), &rest[end + 1..])));
+    }
+    let path = path.strip_prefix('.').unwrap_or(path);
+    let end = path.find(['.', '[']).unwrap_or(path.len());
+    if end == 0 {
+        return Err(fmt::Error);
+    }
+    Ok(Some((Segment::Key(&path[..end]), &path[end..])))
+}
+
+/// Write the value at the path as a document of its own, telling whether it was found
+pub(crate) fn walk<W, T>(
+    writer: &mut W,
+    value: &T,
+    path: &str,
+    config: Config,
+    redaction: Redaction,
+) -> Result<bool, fmt::Error>
+where
+    W: fmt::Write,
+    T: ser::Serialize + ?Sized,
+{
+    match split(path)? {
+        None => document(writer, value, config, redaction).map(|()| true),
+        Some((segment, rest)) => {
+            value.serialize(Walker { writer, config, redaction, segment, rest })
+        }
+    }
+}
+
+/// Whether the map key or struct field reads as the name
+pub(crate) fn key_matches<K: ser::Serialize + ?Sized>(key: &K, name: &str) -> bool {
+    key.serialize(KeyMatch(name)).unwrap_or(false)
+}
+
+/// Descends into the entry matching the segment, writes nothing on its own
+struct Walker<'a, 'p, W: fmt::Write> {
+    writer: &'a mut W,
+    config: Config,
+    redaction: Redaction,
+    segment: Segment<'p>,
+    rest: &'p str,
+}
+
+impl<'a, 'p, W: fmt::Write> Walker<'a, 'p, W> {
+    fn seek(self) -> Seek<'a, 'p, W> {
+        let (segment, rest) = (self.segment, self.rest);
+        Seek::new(self, Some(segment), rest)
+    }
+
+    /// Externally tagged variants are mappings keyed by the variant name,
+    /// tagged ones are looked into directly
+    fn variant(self, variant: &str, mapping: bool) -> Result<Variant<'a, 'p, W>, fmt::Error> {
+        if self.config.enum_style == EnumStyle::Tag {
+            return Ok(Variant::Seek(self.seek()));
+        }
+        let matched = matches!(self.segment, Segment::Key(name) if name == variant);
+        if matched && self.rest.is_empty() {
+            return Body::begin(self, mapping).map(Variant::Body);
+        }
+        let next = if matched { split(self.rest)? } else { None };
+        Ok(Variant::Seek(match next {
+            Some((segment, rest)) => Seek::new(self, Some(segment), rest),
+            None => Seek::new(self, None, ""),
+        }))
+    }
+}
+
+/// Entries of a compound value, of which the one matching the segment is walked
+struct Seek<'a, 'p, W: fmt::Write> {
+    writer: &'a mut W,
+    config: Config,
+    redaction: Redaction,
+    /// None when no entry can match
+    segment: Option<Segment<'p>>,
+    rest: &'p str,
+    index: usize,
+    /// Redaction within the upcoming map value if its key matched,
+    /// none within a redacted value
+    matched: Option<Option<Redaction>>,
+    found: bool,
+}
+
+impl<'a, 'p, W: fmt::Write> Seek<'a, 'p, W> {
+    fn new(walker: Walker<'a, 'p, W>, segment: Option<Segment<'p>>, rest: &'p str) -> Self {
+        let Walker { writer, config, redaction, .. } = walker;
+        Self { writer, config, redaction, segment, rest, index: 0, matched: None, found: false }
+    }
+
+    /// Walk the matching entry, down to the placeholder of a redacted value
+    fn entry<T>(&mut self, redaction: Option<Redaction>, value: &T) -> fmt::Result
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        if self.found {
+            return Ok(());
+        }
+        self.found = match redaction {
+            Some(redaction) => walk(self.writer, value, self.rest, self.config, redaction)?,
+            None => walk(self.writer, PLACEHOLDER, self.rest, self.config, self.redaction)?,
+        };
+        Ok(())
+    }
+
+    fn element<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
+        let matched = self.segment == Some(Segment::Index(self.index));
+        self.index += 1;
+        match matched {
+            true => self.entry(Some(self.redaction), value),
+            false => Ok(()),
+        }
+    }
+
+    fn field<T: ser::Serialize + ?Sized>(&mut self, key: &str, value: &T) -> fmt::Result {
+        match self.segment == Some(Segment::Key(key)) {
+            true => self.entry(self.redaction.enter(self.config.redact, key), value),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Fields of a variant whose name ends the path, written as a document of its own
+///
+/// The fields aren't known upfront, so they're written unaligned and repeated
+/// subtrees aren't anchored automatically.
+struct Body<'a, W: fmt::Write> {
+    serializer: Serializer<&'a mut W>,
+    empty: bool,
+    flow: bool,
+    mapping: bool,
+}
+
+impl<'a, W: fmt::Write> Body<'a, W> {
+    fn begin(walker: Walker<'a, '_, W>, mapping: bool) -> Result<Self, fmt::Error> {
+        let mut serializer = Serializer::new(walker.writer, walker.config);
+        serializer.redaction = walker.redaction;
+        serializer.begin_document()?;
+        let flow = walker.config.flow;
+        if flow {
+            serializer.emitter.begin_flow(if mapping { '{' } else { '[' })?;
+        }
+        Ok(Self { serializer, empty: true, flow, mapping })
+    }
+
+    fn element<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
+        sequence::element(&mut self.serializer, self.empty, self.flow, value)?;
+        self.empty = false;
+        Ok(())
+    }
+
+    fn field<T>(&mut self, key: &'static str, value: &T) -> fmt::Result
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        if struct_::field(&mut self.serializer, self.empty, self.flow, 0, key, value)? {
+            self.empty = false;
+        }
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<bool, fmt::Error> {
+        match self.mapping {
+            true => struct_::end(&mut self.serializer, self.empty, self.flow)?,
+            false => sequence::end(&mut self.serializer, self.empty, self.flow)?,
+        }
+        self.serializer.end_document().map(|()| true)
+    }
+}
+
+/// Fields of a tuple or struct variant, either looked into or written,
+/// only alive while the variant is serialized so the size is of no concern
+#[allow(clippy::large_enum_variant)]
+enum Variant<'a, 'p, W: fmt::Write> {
+    Seek(Seek<'a, 'p, W>),
+    Body(Body<'a, W>),
+}
+
+impl<'a, 'p, W: fmt::Write> ser::Serializer for Walker<'a, 'p, W> {
+    type Ok = bool;
+    type Error = fmt::Error;
+    type SerializeSeq = Seek<'a, 'p, W>;
+    type SerializeTuple = Seek<'a, 'p, W>;
+    type SerializeTupleStruct = Seek<'a, 'p, W>;
+    type SerializeTupleVariant = Variant<'a, 'p, W>;
+    type SerializeMap = Seek<'a, 'p, W>;
+    type SerializeStruct = Seek<'a, 'p, W>;
+    type SerializeStructVariant = Variant<'a, 'p, W>;
+
+    fn serialize_bool(self, _v: bool) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_i128(self, _v: i128) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_u128(self, _v: u128) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_char(self, _c: char) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_none(self) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_some<T: ser::Serialize + ?Sized>(self, value: &T) -> Result<bool, fmt::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<bool, fmt::Error>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ser::Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<bool, fmt::Error> {
+        if self.config.enum_style == EnumStyle::Tag {
+            return value.serialize(self);
+        }
+        match self.segment {
+            Segment::Key(name) if name == variant => {
+                walk(self.writer, value, self.rest, self.config, self.redaction)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, fmt::Error> {
+        Ok(self.seek())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, fmt::Error> {
+        Ok(self.seek())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, fmt::Error> {
+        Ok(self.seek())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, fmt::Error> {
+        self.variant(variant, false)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, fmt::Error> {
+        Ok(self.seek())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, fmt::Error> {
+        Ok(self.seek())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, fmt::Error> {
+        self.variant(variant, true)
+    }
+
+    fn collect_str<T: fmt::Display + ?Sized>(self, _value: &T) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+}
+
+impl<W: fmt::Write> ser::SerializeSeq for Seek<'_, '_, W> {
+    type Ok = bool;
+    type Error = fmt::Error;
+
+    fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<bool, fmt::Error> {
+        Ok(self.found)
+    }
+}
+
+impl<W: fmt::Write> ser::SerializeTuple for Seek<'_, '_, W> {
+    type Ok = bool;
+    type Error = fmt::Error;
+
+    fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<bool, fmt::Error> {
+        Ok(self.found)
+    }
+}
+
+impl<W: fmt::Write> ser::SerializeTupleStruct for Seek<'_, '_, W> {
+    type Ok = bool;
+    type Error = fmt::Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<bool, fmt::Error> {
+        Ok(self.found)
+    }
+}
+
+impl<W: fmt::Write> ser::SerializeTupleVariant for Variant<'_, '_, W> {
+    type Ok = bool;
+    type Error = fmt::Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
+        match self {
+            Variant::Seek(seek) => seek.element(value),
+            Variant::Body(body) => body.element(value),
+        }
+    }
+
+    fn end(self) -> Result<bool, fmt::Error> {
+        match self {
+            Variant::Seek(seek) => Ok(seek.found),
+            Variant::Body(body) => body.end(),
+        }
+    }
+}
+
+impl<W: fmt::Write> ser::SerializeMap for Seek<'_, '_, W> {
+    type Ok = bool;
+    type Error = fmt::Error;
+
+    fn serialize_key<T: ser::Serialize + ?Sized>(&mut self, key: &T) -> fmt::Result {
+        self.matched = match self.segment {
+            Some(Segment::Key(name)) if !self.found && key_matches(key, name) => {
+                Some(self.redaction.enter(self.config.redact, key))
+            }
+            _ => None,
+        };
+        Ok(())
+    }
+
+    fn serialize_value<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
+        match self.matched.take() {
+            Some(redaction) => self.entry(redaction, value),
+            None => Ok(()),
+        }
+    }
+
+    fn end(self) -> Result<bool, fmt::Error> {
+        Ok(self.found)
+    }
+}
+
+impl<W: fmt::Write> ser::SerializeStruct for Seek<'_, '_, W> {
+    type Ok = bool;
+    type Error = fmt::Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> fmt::Result
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<bool, fmt::Error> {
+        Ok(self.found)
+    }
+}
+
+impl<W: fmt::Write> ser::SerializeStructVariant for Variant<'_, '_, W> {
+    type Ok = bool;
+    type Error = fmt::Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> fmt::Result
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        match self {
+            Variant::Seek(seek) => seek.field(key, value),
+            Variant::Body(body) => body.field(key, value),
+        }
+    }
+
+    fn end(self) -> Result<bool, fmt::Error> {
+        match self {
+            Variant::Seek(seek) => Ok(seek.found),
+            Variant::Body(body) => body.end(),
+        }
+    }
+}
+
+/// Tells whether the text written equals the expected text
+struct Compare<'a>(Option<&'a str>);
+
+impl fmt::Write for Compare<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 = self.0.and_then(|rest| rest.strip_prefix(s));
+        Ok(())
+    }
+}
+
+fn matches<T: fmt::Display + ?Sized>(name: &str, key: &T) -> bool {
+    let mut compare = Compare(Some(name));
+    fmt::write(&mut compare, format_args!("{}", key)).is_ok() && compare.0 == Some("")
+}
+
+/// Tells whether a map key reads as the name, complex keys fail to serialize
+struct KeyMatch<'p>(&'p str);
+
+impl ser::Serializer for KeyMatch<'_> {
+    type Ok = bool;
+    type Error = fmt::Error;
+    type SerializeSeq = ser::Impossible<bool, fmt::Error>;
+    type SerializeTuple = ser::Impossible<bool, fmt::Error>;
+    type SerializeTupleStruct = ser::Impossible<bool, fmt::Error>;
+    type SerializeTupleVariant = ser::Impossible<bool, fmt::Error>;
+    type SerializeMap = ser::Impossible<bool, fmt::Error>;
+    type SerializeStruct = ser::Impossible<bool, fmt::Error>;
+    type SerializeStructVariant = ser::Impossible<bool, fmt::Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<bool, fmt::Error> {
+        Ok(matches(self.0, &v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<bool, fmt::Error> {
+        Ok(matches(self.0, &v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<bool, fmt::Error> {
+        Ok(matches(self.0, &v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<bool, fmt::Error> {
+        Ok(matches(self.0, &v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<bool, fmt::Error> {
+        Ok(matches(self.0, &v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<bool, fmt::Error> {
+        Ok(matches(self.0, &v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<bool, fmt::Error> {
+        Ok(matches(self.0, &v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<bool, fmt::Error> {
+        Ok(matches(self.0, &v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<bool, fmt::Error> {
+        Ok(matches(self.0, &v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<bool, fmt::Error> {
+        Ok(matches(self.0, &v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<bool, fmt::Error> {
+        Ok(matches(self.0, &v))
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_char(self, c: char) -> Result<bool, fmt::Error> {
+        Ok(matches(self.0, &c))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<bool, fmt::Error> {
+        Ok(self.0 == v)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_none(self) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_some<T: ser::Serialize + ?Sized>(self, value: &T) -> Result<bool, fmt::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<bool, fmt::Error> {
+        Ok(self.0 == variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<bool, fmt::Error>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ser::Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<bool, fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn collect_str<T: fmt::Display + ?Sized>(self, value: &T) -> Result<bool, fmt::Error> {
+        Ok(matches(self.0, value))
+    }
+}
@@ -0,0 +1,320 @@
+use core::fmt;
+
+use serde::ser;
+
+use crate::ser::marker::Marker;
+use crate::ser::raw;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Kind {
+    Null,
+    Scalar,
+    Compound,
+}
+
+/// Tells what kind of node a value would be written as, without writing anything
+pub(crate) fn kind<T: ser::Serialize + ?Sized>(value: &T) -> Kind {
+    value.serialize(Probe { pending: None, comment: &mut None }).unwrap_or(Kind::Scalar)
+}
+
+pub(crate) fn is_null<T: ser::Serialize + ?Sized>(value: &T) -> bool {
+    kind(value) == Kind::Null
+}
+
+/// Comment to be written above the value, if any
+pub(crate) fn comment<T: ser::Serialize + ?Sized>(value: &T) -> Option<&'static str> {
+    let mut comment = None;
+    value.serialize(Probe { pending: None, comment: &mut comment }).ok();
+    comment
+}
+
+struct Probe<'a> {
+    pending: Option<Marker>,
+    comment: &'a mut Option<&'static str>,
+}
+
+/// Accepts elements of compound values, which are never null
+pub(crate) struct Compound;
+
+impl<'a> ser::Serializer for Probe<'a> {
+    type Ok = Kind;
+    type Error = fmt::Error;
+    type SerializeSeq = Compound;
+    type SerializeTuple = Compound;
+    type SerializeTupleStruct = Compound;
+    type SerializeTupleVariant = Compound;
+    type SerializeMap = Compound;
+    type SerializeStruct = Compound;
+    type SerializeStructVariant = Compound;
+
+    fn serialize_bool(self, _v: bool) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_i128(self, _v: i128) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_u128(self, _v: u128) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_char(self, _c: char) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Kind, fmt::Error> {
+        if self.pending != Some(Marker::Raw) {
+            return Ok(Kind::Scalar);
+        }
+        Ok(match raw::lines(v).next() {
+            Some(line) if raw::is_block(line) => Kind::Compound,
+            Some(_) => Kind::Scalar,
+            None => Kind::Null,
+        })
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_none(self) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Null)
+    }
+
+    fn serialize_some<T: ser::Serialize + ?Sized>(self, value: &T) -> Result<Kind, fmt::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+
+    fn serialize_newtype_struct<T>(
+        mut self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Kind, fmt::Error>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        match self.pending.take() {
+            Some(Marker::Comment) if self.comment.is_none() => *self.comment = Some(name),
+            Some(_) => (),
+            None => self.pending = Marker::of(name).filter(|m| !matches!(m, Marker::Override(_))),
+        }
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ser::Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Compound)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound, fmt::Error> {
+        Ok(Compound)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Compound, fmt::Error> {
+        Ok(Compound)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Compound, fmt::Error> {
+        Ok(Compound)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound, fmt::Error> {
+        Ok(Compound)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound, fmt::Error> {
+        Ok(Compound)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Compound, fmt::Error> {
+        Ok(Compound)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound, fmt::Error> {
+        Ok(Compound)
+    }
+
+    fn collect_str<T: fmt::Display + ?Sized>(self, _value: &T) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Scalar)
+    }
+}
+
+impl ser::SerializeSeq for Compound {
+    type Ok = Kind;
+    type Error = fmt::Error;
+
+    fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, _value: &T) -> fmt::Result {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Compound)
+    }
+}
+
+impl ser::SerializeTuple for Compound {
+    type Ok = Kind;
+    type Error = fmt::Error;
+
+    fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, _value: &T) -> fmt::Result {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Compound)
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound {
+    type Ok = Kind;
+    type Error = fmt::Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(&mut self, _value: &T) -> fmt::Result {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Compound)
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound {
+    type Ok = Kind;
+    type Error = fmt::Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(&mut self, _value: &T) -> fmt::Result {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Compound)
+    }
+}
+
+impl ser::SerializeMap for Compound {
+    type Ok = Kind;
+    type Error = fmt::Error;
+
+    fn serialize_key<T: ser::Serialize + ?Sized>(&mut self, _key: &T) -> fmt::Result {
+        Ok(())
+    }
+
+    fn serialize_value<T: ser::Serialize + ?Sized>(&mut self, _value: &T) -> fmt::Result {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Compound)
+    }
+}
+
+impl ser::SerializeStruct for Compound {
+    type Ok = Kind;
+    type Error = fmt::Error;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, _value: &T) -> fmt::Result
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Compound)
+    }
+}
+
+impl ser::SerializeStructVariant for Compound {
+    type Ok = Kind;
+    type Error = fmt::Error;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, _value: &T) -> fmt::Result
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Kind, fmt::Error> {
+        Ok(Kind::Compound)
+    }
+}
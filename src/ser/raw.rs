@@ -0,0 +1,91 @@
+use core::fmt;
+
+use serde::ser;
+
+use crate::ser::marker::Marker;
+use crate::ser::{Element, Serializer};
+
+/// Pre-formatted YAML embedded verbatim, re-indented to where it's placed
+///
+/// Leading blank lines are left out, other lines are kept as they are, and a final
+/// line break ends the line where it's placed, e.g. for keep block scalars. Block
+/// mappings, block sequences and multi-line fragments can't be placed in flow
+/// collections, serializing fails there. Other serializers see a string.
+///
+/// ```
+/// use serde_yaml_core::ser::RawYaml;
+///
+/// #[derive(serde_derive::Serialize)]
+/// struct Config<'a> {
+///     network: RawYaml<'a>,
+/// }
+///
+/// let config = Config { network: RawYaml("dhcp: true\nmtu: 1500\n") };
+/// let mut yaml = String::new();
+/// serde_yaml_core::to_fmt(&mut yaml, &config).unwrap();
+/// assert_eq!(yaml, "network:\n  dhcp: true\n  mtu: 1500\n");
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct RawYaml<'a>(pub &'a str);
+
+impl ser::Serialize for RawYaml<'_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(Marker::Raw.name(), self.0)
+    }
+}
+
+/// Whether the fragment starting with this line is a block mapping or sequence
+pub(crate) fn is_block(first_line: &str) -> bool {
+    let first_line = first_line.trim_end();
+    match first_line.chars().next() {
+        Some('-') | Some('?') => matches!(first_line.chars().nth(1), None | Some(' ')),
+        Some('[') | Some('{') | Some('"') | Some('\'') | Some('|') | Some('>') => false,
+        Some(_) => first_line.ends_with(':') || first_line.contains(": "),
+        None => false,
+    }
+}
+
+/// Lines of the fragment with leading blank lines and common indentation removed
+pub(crate) fn lines(yaml: &str) -> impl Iterator<Item = &str> {
+    let blank = |line: &&str| line.trim().is_empty();
+    let indentation = |line: &str| line.len() - line.trim_start().len();
+    let margin = yaml.lines().filter(|line| !blank(line)).map(indentation).min().unwrap_or(0);
+    yaml.lines().skip_while(blank).map(move |line| line.get(margin..).unwrap_or(""))
+}
+
+impl<W: fmt::Write> Serializer<W> {
+    pub(crate) fn raw(&mut self, yaml: &str) -> fmt::Result {
+        if self.emitter.config.ascii_only && !yaml.is_ascii() {
+            return Err(fmt::Error);
+        }
+        let flow = self.emitter.flow > 0;
+        // Trailing line breaks and blank lines are of no meaning to flow scalars
+        let yaml = if flow { yaml.trim_end() } else { yaml };
+        let mut lines = lines(yaml);
+        let first = match lines.next() {
+            Some(line) => line,
+            None => return self.emitter.write_null(),
+        };
+        let block = is_block(first);
+        let mut lines = lines.peekable();
+        if flow && (block || lines.peek().is_some()) {
+            return Err(fmt::Error);
+        }
+        let element = if block { Element::MappingKey } else { Element::Literal };
+        self.emitter.indent(element)?;
+        self.emitter.str(first)?;
+        for line in lines {
+            self.emitter.newline()?;
+            if !line.is_empty() {
+                self.emitter.margin()?;
+                self.emitter.str(line)?;
+            }
+        }
+        self.emitter.preceding_element = Element::Literal;
+        if yaml.ends_with(['\n', '\r']) {
+            self.emitter.newline()?;
+            self.emitter.line_ended = true;
+        }
+        Ok(())
+    }
+}
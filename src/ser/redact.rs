@@ -0,0 +1,76 @@
+use core::fmt;
+
+use serde::ser;
+
+use crate::ser::path::key_matches;
+
+/// Written in place of redacted values
+pub(crate) const PLACEHOLDER: &str = "***";
+
+/// Patterns with dots followed along the keys at once, one bit each
+pub(crate) const MAX_PATHS: usize = 32;
+
+/// Number of patterns with dots, i.e. paths of keys
+pub(crate) const fn paths(patterns: &[&str]) -> usize {
+    let (mut count, mut i) = (0, 0);
+    while i < patterns.len() {
+        let bytes = patterns[i].as_bytes();
+        let mut j = 0;
+        while j < bytes.len() && bytes[j] != b'.' {
+            j += 1;
+        }
+        count += (j < bytes.len()) as usize;
+        i += 1;
+    }
+    count
+}
+
+/// Patterns with dots whose leading keys match the keys leading to the value ahead
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct Redaction {
+    matching: u32,
+    depth: usize,
+}
+
+impl Redaction {
+    /// Every pattern with dots matches at the root of the document
+    pub(crate) fn root(patterns: &[&str]) -> Result<Self, fmt::Error> {
+        let paths = paths(patterns);
+        if paths > MAX_PATHS {
+            return Err(fmt::Error);
+        }
+        let matching = if paths == 0 { 0 } else { u32::MAX >> (MAX_PATHS - paths) };
+        Ok(Self { matching, depth: 0 })
+    }
+
+    /// Redaction within the value of the key, none if the value is redacted
+    pub(crate) fn enter<K>(self, patterns: &[&str], key: &K) -> Option<Self>
+    where
+        K: ser::Serialize + ?Sized,
+    {
+        let mut matching = 0;
+        let mut bit = 1;
+        for pattern in patterns {
+            if !pattern.contains('.') {
+                if key_matches(key, pattern) {
+                    return None;
+                }
+                continue;
+            }
+            let path = bit;
+            bit <<= 1;
+            if self.matching & path == 0 {
+                continue;
+            }
+            let mut segments = pattern.split('.').skip(self.depth);
+            match segments.next() {
+                Some(segment) if segment == "*" || key_matches(key, segment) => (),
+                _ => continue,
+            }
+            // The value is redacted when the last segment matched
+            segments.next()?;
+            matching |= path;
+        }
+        Some(Self { matching, depth: self.depth + 1 })
+    }
+}
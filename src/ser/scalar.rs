@@ -0,0 +1,310 @@
+use core::fmt;
+
+/// Presentation style of a scalar
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Style {
+    Plain,
+    SingleQuoted,
+    DoubleQuoted,
+}
+
+/// Plain scalars YAML 1.1 or 1.2 resolves into null or bool,
+/// single letter y and n are left out since they are seldom treated as bool
+const RESERVED: &[&str] = &[
+    "~", "null", "Null", "NULL", "true", "True", "TRUE", "false", "False", "FALSE", "yes", "Yes",
+    "YES", "no", "No", "NO", "on", "On", "ON", "off", "Off", "OFF",
+];
+
+fn is_indicator(c: char) -> bool {
+    matches!(
+        c,
+        ',' | '['
+            | ']'
+            | '{'
+            | '}'
+            | '#'
+            | '&'
+            | '*'
+            | '!'
+            | '|'
+            | '>'
+            | '\''
+            | '"'
+            | '%'
+            | '@'
+            | '`'
+    )
+}
+
+/// Numbers in YAML 1.1 may contain underscores, e.g. `1_000` or `0x_FF`,
+/// anything alike is treated as such
+fn is_underscored_number(unsigned: &str) -> bool {
+    let number_char = |c: char| {
+        c.is_ascii_hexdigit() || matches!(c, '_' | '.' | ':' | 'x' | 'o' | 'e' | 'E' | '+' | '-')
+    };
+    unsigned.contains('_')
+        && unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && unsigned.chars().all(number_char)
+}
+
+fn is_number(v: &str) -> bool {
+    if v.chars().all(|c| c.is_ascii_digit() || c == ':') || v.parse::<f64>().is_ok() {
+        return true;
+    }
+    let unsigned = v.trim_start_matches(['-', '+']);
+    if is_underscored_number(unsigned) {
+        return true;
+    }
+    match unsigned {
+        ".inf" | ".Inf" | ".INF" | ".nan" | ".NaN" | ".NAN" => return true,
+        _ => (),
+    }
+    let radix = ["0x", "0o", "0b"].iter().any(|prefix| unsigned.starts_with(prefix));
+    radix && unsigned[2..].chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl Style {
+    /// Resolve the simplest style which reads back as the very same string,
+    /// plain scalars in flow collections must not contain flow indicators
+    pub(crate) fn of(v: &str, flow: bool) -> Self {
+        if v.chars().any(char::is_control) {
+            return Style::DoubleQuoted;
+        }
+        let first = match v.chars().next() {
+            Some(c) => c,
+            None => return Style::SingleQuoted,
+        };
+        let second = v[first.len_utf8()..].chars().next();
+        let quote = match first {
+            '-' | '?' | ':' => second.map(|c| c == ' ').unwrap_or(true),
+            ' ' => true,
+            c => is_indicator(c),
+        };
+        if quote
+            || v.starts_with("---")
+            || v.starts_with("...")
+            || v.ends_with(' ')
+            || v.ends_with(':')
+            || v.contains(": ")
+            || v.contains(" #")
+            || (flow && v.contains([',', '[', ']', '{', '}']))
+            || RESERVED.contains(&v)
+            || is_number(v)
+        {
+            return Style::SingleQuoted;
+        }
+        Style::Plain
+    }
+}
+
+pub(crate) fn write_single_quoted<W: fmt::Write>(w: &mut W, v: &str) -> fmt::Result {
+    w.write_char('\'')?;
+    for (i, part) in v.split('\'').enumerate() {
+        if i > 0 {
+            w.write_str("''")?;
+        }
+        w.write_str(part)?;
+    }
+    w.write_char('\'')
+}
+
+/// Capacity of `Buffer`, enough for addresses, timestamps and the like
+const BUFFER_LEN: usize = 128;
+
+/// Text formatted on the stack, writing fails once it doesn't fit
+pub(crate) struct Buffer {
+    bytes: [u8; BUFFER_LEN],
+    len: usize,
+}
+
+impl Buffer {
+    pub(crate) fn new() -> Self {
+        Self { bytes: [0; BUFFER_LEN], len: 0 }
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        // Only whole strings are copied in, so the bytes are always valid UTF-8
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+}
+
+impl fmt::Write for Buffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        let bytes = self.bytes.get_mut(self.len..end).ok_or(fmt::Error)?;
+        bytes.copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Escapes text written within a double-quoted scalar
+pub(crate) struct Escaped<'a, W: fmt::Write> {
+    writer: &'a mut W,
+    ascii: bool,
+}
+
+impl<'a, W: fmt::Write> Escaped<'a, W> {
+    /// Non-ASCII characters are escaped as well when `ascii` is set
+    pub(crate) fn new(writer: &'a mut W, ascii: bool) -> Self {
+        Self { writer, ascii }
+    }
+}
+
+impl<W: fmt::Write> fmt::Write for Escaped<'_, W> {
+    fn write_str(&mut self, v: &str) -> fmt::Result {
+        let (w, ascii) = (&mut *self.writer, self.ascii);
+        for c in v.chars() {
+            match c {
+                '"' => w.write_str("\\\"")?,
+                '\\' => w.write_str("\\\\")?,
+                '\0' => w.write_str("\\0")?,
+                '\t' => w.write_str("\\t")?,
+                '\n' => w.write_str("\\n")?,
+                '\r' => w.write_str("\\r")?,
+                c if (c as u32) < 0x100 && c.is_control() => write!(w, "\\x{:02X}", c as u32)?,
+                c if c.is_control() || (ascii && (c as u32) < 0x10000 && !c.is_ascii()) => {
+                    write!(w, "\\u{:04X}", c as u32)?
+                }
+                c if ascii && !c.is_ascii() => write!(w, "\\U{:08X}", c as u32)?,
+                c => w.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Non-ASCII characters are escaped as well when `ascii` is set
+pub(crate) fn write_double_quoted<W: fmt::Write>(w: &mut W, v: &str, ascii: bool) -> fmt::Result {
+    w.write_char('"')?;
+    fmt::Write::write_str(&mut Escaped::new(w, ascii), v)?;
+    w.write_char('"')
+}
+
+/// Non-ASCII strings are double-quoted with escapes when `ascii` is set
+pub(crate) fn write<W: fmt::Write>(w: &mut W, v: &str, flow: bool, ascii: bool) -> fmt::Result {
+    if ascii && !v.is_ascii() {
+        return write_double_quoted(w, v, ascii);
+    }
+    match Style::of(v, flow) {
+        Style::Plain => w.write_str(v),
+        Style::SingleQuoted => write_single_quoted(w, v),
+        Style::DoubleQuoted => write_double_quoted(w, v, ascii),
+    }
+}
+
+/// Write a local tag, percent-encoding characters not allowed in a tag
+pub(crate) fn write_tag<W: fmt::Write>(w: &mut W, tag: &str) -> fmt::Result {
+    w.write_char('!')?;
+    for c in tag.chars() {
+        match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => w.write_char(c)?,
+            '-' | '#' | ';' | '/' | '?' | ':' | '@' | '&' | '=' | '+' | '$' | '_' | '.' | '~' => {
+                w.write_char(c)?
+            }
+            '*' | '\'' | '(' | ')' => w.write_char(c)?,
+            c => {
+                for byte in c.encode_utf8(&mut [0u8; 4]).bytes() {
+                    write!(w, "%{:02X}", byte)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Lines of folded block scalars are wrapped beyond this width
+const FOLD_WIDTH: usize = 80;
+
+/// Whether the string reads back the same as a literal block scalar
+pub(crate) fn is_block_compatible(v: &str) -> bool {
+    let first = v.trim_start_matches('\n');
+    !first.is_empty()
+        && !first.starts_with([' ', '\t'])
+        && !v.chars().any(|c| c.is_control() && c != '\n' && c != '\t')
+}
+
+/// Write a line of folded block scalar, breaking at single spaces between words
+fn write_folded_line<W>(w: &mut W, line: &str, indent: usize, eol: &str) -> fmt::Result
+where
+    W: fmt::Write,
+{
+    let bytes = line.as_bytes();
+    let breakable = |i: usize| {
+        bytes[i] == b' '
+            && i > 0
+            && bytes[i - 1] != b' '
+            && bytes.get(i + 1).is_some_and(|&b| b != b' ')
+    };
+    let mut start = 0;
+    let mut candidate = None;
+    for i in (0..bytes.len()).filter(|&i| breakable(i)) {
+        if i - start > FOLD_WIDTH {
+            if let Some(at) = candidate {
+                write!(w, "{}{}{:indent$}", &line[start..at], eol, "", indent = indent)?;
+                start = at + 1;
+            }
+        }
+        candidate = Some(i);
+    }
+    if line.len() - start > FOLD_WIDTH {
+        if let Some(at) = candidate.filter(|&at| at > start) {
+            write!(w, "{}{}{:indent$}", &line[start..at], eol, "", indent = indent)?;
+            start = at + 1;
+        }
+    }
+    w.write_str(&line[start..])
+}
+
+/// Write literal `|` or folded `>` block scalar with content indented,
+/// folded falls back to literal when any line starts with whitespace,
+/// line breaks are written as `eol`
+///
+/// Unless the trailing line break is stripped, the last line is ended here,
+/// so that the scalar reads back the same at the end of the document too
+pub(crate) fn write_block<W: fmt::Write>(
+    w: &mut W,
+    v: &str,
+    folded: bool,
+    indent: usize,
+    eol: &str,
+) -> fmt::Result {
+    let body = v.trim_end_matches('\n');
+    let folded = folded && !body.split('\n').any(|line| line.starts_with([' ', '\t']));
+    w.write_char(if folded { '>' } else { '|' })?;
+    let trailing = v.len() - body.len();
+    match trailing {
+        0 => w.write_char('-')?,
+        1 => (),
+        _ => w.write_char('+')?,
+    }
+    // Line breaks of folded scalars are written as empty lines, since
+    // a single line break between two lines is folded into a space
+    let mut breaks = 0;
+    for (i, line) in body.split('\n').enumerate() {
+        if i > 0 {
+            breaks += 1;
+        }
+        if folded && line.is_empty() {
+            continue;
+        }
+        w.write_str(eol)?;
+        if folded {
+            for _ in 0..breaks {
+                w.write_str(eol)?;
+            }
+            breaks = 0;
+        }
+        if !line.is_empty() {
+            write!(w, "{:indent$}", "", indent = indent)?;
+            match folded {
+                true => write_folded_line(w, line, indent, eol)?,
+                false => w.write_str(line)?,
+            }
+        }
+    }
+    for _ in 0..trailing {
+        w.write_str(eol)?;
+    }
+    Ok(())
+}
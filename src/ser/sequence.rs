@@ -4,14 +4,55 @@ use serde::ser;
 
 use crate::ser::{Element, Serializer};
 
+/// Write an element of a sequence, first telling whether it's the first one
+pub(crate) fn element<W, T>(
+    serializer: &mut Serializer<W>,
+    first: bool,
+    flow: bool,
+    value: &T,
+) -> fmt::Result
+where
+    W: fmt::Write,
+    T: ser::Serialize + ?Sized,
+{
+    serializer.emitter.separate(first, flow)?;
+    if flow {
+        return serializer.value(value);
+    }
+    serializer.comment_ahead(value)?;
+    serializer.emitter.indent(Element::SequenceEntry)?;
+    serializer.emitter.str("-")?;
+    serializer.emitter.push();
+    serializer.value(value)?;
+    serializer.emitter.pop();
+    Ok(())
+}
+
+/// Close a sequence, empty block sequences are written in flow style
+pub(crate) fn end<W: fmt::Write>(
+    serializer: &mut Serializer<W>,
+    empty: bool,
+    flow: bool,
+) -> fmt::Result {
+    if flow {
+        return serializer.emitter.end_flow(']');
+    }
+    if empty {
+        serializer.emitter.indent(Element::Literal)?;
+        serializer.emitter.str("[]")?;
+    }
+    Ok(())
+}
+
 pub struct SerializeSeq<'a, W: fmt::Write> {
     serializer: &'a mut Serializer<W>,
     empty: bool,
+    flow: bool,
 }
 
 impl<'a, W: fmt::Write> SerializeSeq<'a, W> {
-    pub(crate) fn new(serializer: &'a mut Serializer<W>) -> Self {
-        Self { serializer, empty: true }
+    pub(crate) fn new(serializer: &'a mut Serializer<W>, flow: bool) -> Self {
+        Self { serializer, empty: true, flow }
     }
 }
 
@@ -20,23 +61,13 @@ impl<'a, W: fmt::Write> ser::SerializeSeq for SerializeSeq<'a, W> {
     type Error = fmt::Error;
 
     fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
-        if !self.empty {
-            self.serializer.char('\n')?;
-        }
+        element(self.serializer, self.empty, self.flow, value)?;
         self.empty = false;
-        self.serializer.indent(Element::SequenceEntry)?;
-        self.serializer.str("-")?;
-        self.serializer.push();
-        value.serialize(&mut *self.serializer)?;
-        self.serializer.pop();
         Ok(())
     }
 
     fn end(self) -> fmt::Result {
-        if self.empty {
-            self.serializer.str("[]")?;
-        }
-        Ok(())
+        end(self.serializer, self.empty, self.flow)
     }
 }
 
@@ -52,3 +83,52 @@ impl<'a, W: fmt::Write> ser::SerializeTuple for SerializeSeq<'a, W> {
         ser::SerializeSeq::end(self)
     }
 }
+
+impl<'a, W: fmt::Write> ser::SerializeTupleStruct for SerializeSeq<'a, W> {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> fmt::Result {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+pub struct SerializeTupleVariant<'a, W: fmt::Write> {
+    serializer: &'a mut Serializer<W>,
+    empty: bool,
+    flow: bool,
+    keyed: bool,
+}
+
+impl<'a, W: fmt::Write> SerializeTupleVariant<'a, W> {
+    /// Keyed when the variant name is written as a mapping key rather than a tag
+    pub(crate) fn new(serializer: &'a mut Serializer<W>, flow: bool, keyed: bool) -> Self {
+        Self { serializer, empty: true, flow, keyed }
+    }
+}
+
+impl<'a, W: fmt::Write> ser::SerializeTupleVariant for SerializeTupleVariant<'a, W> {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
+        element(self.serializer, self.empty, self.flow, value)?;
+        self.empty = false;
+        Ok(())
+    }
+
+    fn end(self) -> fmt::Result {
+        end(self.serializer, self.empty, self.flow)?;
+        if self.keyed && self.flow {
+            return self.serializer.emitter.end_flow('}');
+        }
+        if self.keyed {
+            self.serializer.emitter.pop();
+        }
+        Ok(())
+    }
+}
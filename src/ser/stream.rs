@@ -0,0 +1,67 @@
+use core::fmt;
+
+use serde::ser;
+
+use crate::ser::redact::Redaction;
+use crate::ser::{sequence, Config, Serializer};
+
+/// Writes a top-level sequence item by item, e.g. samples logged over hours
+///
+/// Items are written as they're pushed, the output matches serializing all of them
+/// as one sequence. The line break ahead of an item is written when it's pushed, and
+/// an empty sequence, closing bracket or document end marker when finishing.
+/// Repeated subtrees aren't anchored automatically, since they aren't known upfront.
+///
+/// ```
+/// use serde_yaml_core::ser::SeqWriter;
+///
+/// let mut writer = SeqWriter::new(String::new());
+/// for sample in [215, 218] {
+///     writer.push(&sample).unwrap();
+/// }
+/// assert_eq!(writer.finish().unwrap(), "- 215\n- 218");
+/// ```
+pub struct SeqWriter<W: fmt::Write> {
+    serializer: Serializer<W>,
+    empty: bool,
+    flow: bool,
+}
+
+impl<W: fmt::Write> SeqWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_config(writer, Config::new())
+    }
+
+    pub fn with_config(writer: W, config: Config) -> Self {
+        Self { serializer: Serializer::new(writer, config), empty: true, flow: config.flow }
+    }
+
+    fn begin(&mut self) -> fmt::Result {
+        self.serializer.redaction = Redaction::root(self.serializer.emitter.config.redact)?;
+        self.serializer.begin_document()?;
+        if self.flow {
+            self.serializer.emitter.begin_flow('[')?;
+        }
+        Ok(())
+    }
+
+    /// Write the next item of the sequence
+    pub fn push<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> fmt::Result {
+        if self.empty {
+            self.begin()?;
+        }
+        sequence::element(&mut self.serializer, self.empty, self.flow, value)?;
+        self.empty = false;
+        Ok(())
+    }
+
+    /// Close the sequence and the document, returning the writer
+    pub fn finish(mut self) -> Result<W, fmt::Error> {
+        if self.empty {
+            self.begin()?;
+        }
+        sequence::end(&mut self.serializer, self.empty, self.flow)?;
+        self.serializer.end_document()?;
+        Ok(self.serializer.emitter.into_inner())
+    }
+}
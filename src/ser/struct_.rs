@@ -2,16 +2,64 @@ use core::fmt;
 
 use serde::ser;
 
+use crate::ser::probe::is_null;
 use crate::ser::{Element, Serializer};
 
+/// Write a field of a struct, first telling whether it's the first one written,
+/// returns whether it was written since null values are left out with `omit_null`
+pub(crate) fn field<W, T>(
+    serializer: &mut Serializer<W>,
+    first: bool,
+    flow: bool,
+    align: usize,
+    key: &'static str,
+    value: &T,
+) -> Result<bool, fmt::Error>
+where
+    W: fmt::Write,
+    T: ser::Serialize + ?Sized,
+{
+    if serializer.emitter.config.omit_null && is_null(value) {
+        return Ok(false);
+    }
+    serializer.emitter.separate(first, flow)?;
+    serializer.comment_ahead(value)?;
+    serializer.emitter.write_key(key)?;
+    serializer.align(align, key);
+    serializer.emitter.push();
+    let redaction = serializer.enter(key);
+    serializer.entry_value(redaction, value)?;
+    serializer.emitter.pop();
+    Ok(true)
+}
+
+/// Close a struct, empty block structs are written in flow style
+pub(crate) fn end<W: fmt::Write>(
+    serializer: &mut Serializer<W>,
+    empty: bool,
+    flow: bool,
+) -> fmt::Result {
+    if flow {
+        return serializer.emitter.end_flow('}');
+    }
+    if empty {
+        serializer.emitter.indent(Element::Literal)?;
+        serializer.emitter.str("{}")?;
+    }
+    Ok(())
+}
+
 pub struct SerializeStruct<'a, W: fmt::Write> {
     serializer: &'a mut Serializer<W>,
     empty: bool,
+    flow: bool,
+    align: usize,
 }
 
 impl<'a, W: fmt::Write> SerializeStruct<'a, W> {
-    pub(crate) fn new(serializer: &'a mut Serializer<W>) -> Self {
-        Self { serializer, empty: true }
+    pub(crate) fn new(serializer: &'a mut Serializer<W>, flow: bool) -> Self {
+        let align = core::mem::take(&mut serializer.align);
+        Self { serializer, empty: true, flow, align }
     }
 }
 
@@ -23,34 +71,30 @@ impl<'a, W: fmt::Write> ser::SerializeStruct for SerializeStruct<'a, W> {
     where
         T: ser::Serialize + ?Sized,
     {
-        if !self.empty {
-            self.serializer.char('\n')?;
+        if field(self.serializer, self.empty, self.flow, self.align, key, value)? {
+            self.empty = false;
         }
-        self.empty = false;
-        self.serializer.indent(Element::MappingKey)?;
-        write!(self.serializer.writer, "{}:", key)?;
-        self.serializer.push();
-        value.serialize(&mut *self.serializer)?;
-        self.serializer.pop();
         Ok(())
     }
 
     fn end(self) -> fmt::Result {
-        if self.empty {
-            self.serializer.str("{}")?;
-        }
-        Ok(())
+        end(self.serializer, self.empty, self.flow)
     }
 }
 
 pub struct SerializeStructVariant<'a, W: fmt::Write> {
     serializer: &'a mut Serializer<W>,
     empty: bool,
+    flow: bool,
+    keyed: bool,
+    align: usize,
 }
 
 impl<'a, W: fmt::Write> SerializeStructVariant<'a, W> {
-    pub(crate) fn new(serializer: &'a mut Serializer<W>) -> Self {
-        Self { serializer, empty: true }
+    /// Keyed when the variant name is written as a mapping key rather than a tag
+    pub(crate) fn new(serializer: &'a mut Serializer<W>, flow: bool, keyed: bool) -> Self {
+        let align = core::mem::take(&mut serializer.align);
+        Self { serializer, empty: true, flow, keyed, align }
     }
 }
 
@@ -62,23 +106,20 @@ impl<'a, W: fmt::Write> ser::SerializeStructVariant for SerializeStructVariant<'
     where
         T: ser::Serialize + ?Sized,
     {
-        if !self.empty {
-            self.serializer.char('\n')?;
+        if field(self.serializer, self.empty, self.flow, self.align, key, value)? {
+            self.empty = false;
         }
-        self.empty = false;
-        self.serializer.indent(Element::MappingKey)?;
-        write!(self.serializer.writer, "{}:", key)?;
-        self.serializer.push();
-        value.serialize(&mut *self.serializer)?;
-        self.serializer.pop();
         Ok(())
     }
 
     fn end(self) -> fmt::Result {
-        if self.empty {
-            self.serializer.str("{}")?;
+        end(self.serializer, self.empty, self.flow)?;
+        if self.keyed && self.flow {
+            return self.serializer.emitter.end_flow('}');
+        }
+        if self.keyed {
+            self.serializer.emitter.pop();
         }
-        self.serializer.pop();
         Ok(())
     }
 }
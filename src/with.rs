@@ -0,0 +1,110 @@
+//! Style overrides for a single value, e.g. `#[serde(serialize_with = "with::literal")]`
+//!
+//! Other serializers see the value as is.
+//!
+//! ```
+//! use serde_yaml_core::with;
+//!
+//! #[derive(serde_derive::Serialize)]
+//! struct Register<'a> {
+//!     #[serde(serialize_with = "with::hex")]
+//!     address: u32,
+//!     #[serde(serialize_with = "with::flow")]
+//!     bits: [u8; 3],
+//!     #[serde(serialize_with = "with::literal")]
+//!     description: &'a str,
+//! }
+//!
+//! let description = "Control\nregister\n";
+//! let register = Register { address: 0x4000, bits: [0, 1, 2], description };
+//! let mut yaml = String::new();
+//! serde_yaml_core::to_fmt(&mut yaml, &register).unwrap();
+//! assert_eq!(yaml, "address: 0x4000\nbits: [0, 1, 2]\ndescription: |\n  Control\n  register\n");
+//! ```
+
+use serde::ser;
+
+use crate::ser::marker::{Marker, Override};
+use crate::ser::Radix;
+
+fn overridden<T, S>(style: Override, value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ser::Serialize + ?Sized,
+    S: ser::Serializer,
+{
+    serializer.serialize_newtype_struct(Marker::Override(style).name(), value)
+}
+
+/// String as literal block scalar `|`, preserving line breaks,
+/// falls back to double-quoted where not representable
+pub fn literal<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ser::Serialize + ?Sized,
+    S: ser::Serializer,
+{
+    overridden(Override::Literal, value, serializer)
+}
+
+/// String as folded block scalar `>`, with long lines wrapped at spaces,
+/// falls back to literal or double-quoted where not representable
+pub fn folded<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ser::Serialize + ?Sized,
+    S: ser::Serializer,
+{
+    overridden(Override::Folded, value, serializer)
+}
+
+/// String or char as double-quoted scalar with escapes
+pub fn double_quoted<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ser::Serialize + ?Sized,
+    S: ser::Serializer,
+{
+    overridden(Override::DoubleQuoted, value, serializer)
+}
+
+/// Sequence, mapping or struct in flow style, e.g. `[1, 2]` or `{x: 1, y: 2}`
+pub fn flow<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ser::Serialize + ?Sized,
+    S: ser::Serializer,
+{
+    overridden(Override::Flow, value, serializer)
+}
+
+/// Integer in hexadecimal, e.g. `0x1F`
+pub fn hex<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ser::Serialize + ?Sized,
+    S: ser::Serializer,
+{
+    overridden(Override::Radix { radix: Radix::Hex, padded: false }, value, serializer)
+}
+
+/// Integer in octal, e.g. `0o17`
+pub fn octal<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ser::Serialize + ?Sized,
+    S: ser::Serializer,
+{
+    overridden(Override::Radix { radix: Radix::Octal, padded: false }, value, serializer)
+}
+
+/// Integer in binary, e.g. `0b101`
+pub fn binary<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ser::Serialize + ?Sized,
+    S: ser::Serializer,
+{
+    overridden(Override::Radix { radix: Radix::Binary, padded: false }, value, serializer)
+}
+
+/// Integer in decimal regardless of `Config::unsigned_radix`
+pub fn decimal<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ser::Serialize + ?Sized,
+    S: ser::Serializer,
+{
+    overridden(Override::Radix { radix: Radix::Decimal, padded: false }, value, serializer)
+}